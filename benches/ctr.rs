@@ -0,0 +1,37 @@
+//! Counter mode throughput, sequential and (with the parallel feature) across all cores
+//!
+//! Run with `cargo bench --bench ctr --features parallel` to compare the two paths
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use cryptopals::cyphers::aes::cipher::Aes;
+use cryptopals::cyphers::modes::ctr::Ctr;
+
+const SIZES: [usize; 3] = [16 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+
+fn ctr_throughput(c: &mut Criterion) {
+    let ctr = Ctr::new(Aes::new("YELLOW SUBMARINE".as_bytes()).unwrap());
+    let mut group = c.benchmark_group("ctr");
+    group.sample_size(10);
+
+    for size in SIZES {
+        let plain_text = vec![0x5a; size];
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("sequential", size),
+            &plain_text,
+            |b, data| b.iter(|| ctr.apply_keystream(black_box(data), 0)),
+        );
+
+        #[cfg(feature = "parallel")]
+        group.bench_with_input(
+            BenchmarkId::new("parallel", size),
+            &plain_text,
+            |b, data| b.iter(|| ctr.par_apply_keystream(black_box(data), 0)),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, ctr_throughput);
+criterion_main!(benches);
@@ -0,0 +1,110 @@
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse};
+use serde::Deserialize;
+
+use cryptopals::cyphers::aes::ctr::CTRSampleEncryptions;
+use cryptopals::cyphers::aes::oracles::edit_oracle::http::{decode_token, encode_token};
+use cryptopals::cyphers::aes::oracles::edit_oracle::EditOracle;
+use cryptopals::encoding::hex::Hex;
+use cryptopals::encoding::Digest;
+
+/// The longest edit accepted in a single request
+const MAX_EDIT_LENGTH: usize = 16;
+const SECRET: &str =
+    "Now that the party is jumping, with the bass kicked in and the Vega's are pumpin'";
+
+#[derive(Deserialize)]
+pub(crate) struct Edit {
+    token: String,
+    offset: usize,
+    text: String,
+}
+
+/// Hands out a token (hex encoded big endian nonce followed by the cipher text) of the secret
+#[get("/ctr-edit/token")]
+pub(crate) async fn issue_token(oracle: Data<CTRSampleEncryptions>) -> HttpResponse {
+    let message = oracle.encrypt(&SECRET.as_bytes());
+    HttpResponse::Ok().body(encode_token(&message))
+}
+
+/// Replaces the plain text of a token at the offset with the hex encoded text, responding with the
+/// edited token
+///
+/// 413 if the text is longer than 16 bytes and 400 if the token or text is malformed or the edit
+/// runs past the end of the message
+#[get("/ctr-edit")]
+pub(crate) async fn edit(edit: Query<Edit>, oracle: Data<CTRSampleEncryptions>) -> HttpResponse {
+    let message = match decode_token(&edit.token) {
+        Ok(message) => message,
+        Err(_) => return HttpResponse::BadRequest().body("token is malformed"),
+    };
+    let text = match edit.text.parse::<Hex>() {
+        Ok(text) => text,
+        Err(_) => return HttpResponse::BadRequest().body("text is not valid hex"),
+    };
+    if text.bytes().len() > MAX_EDIT_LENGTH {
+        return HttpResponse::PayloadTooLarge().body("edits are limited to 16 bytes");
+    }
+
+    match EditOracle::edit(oracle.as_ref(), &message, edit.offset, text.bytes()) {
+        Ok(message) => HttpResponse::Ok().body(encode_token(&message)),
+        Err(_) => HttpResponse::BadRequest().body("edit runs past the end of the message"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use actix_web::web::Data;
+    use actix_web::{rt, App, HttpServer};
+
+    use cryptopals::cyphers::aes::ctr::CTRSampleEncryptions;
+    use cryptopals::cyphers::aes::oracles::edit_oracle::http::{decode_token, HttpEditOracle};
+    use cryptopals::cyphers::aes::oracles::edit_oracle::{recover_plain_text, EditOracle};
+
+    use crate::ctr_edit::{edit, issue_token, SECRET};
+
+    /// Runs the routes on a free port and returns the address to reach them
+    fn serve() -> String {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            rt::System::new().block_on(async move {
+                let oracle = Data::new(CTRSampleEncryptions::new());
+                let server = HttpServer::new(move || {
+                    App::new()
+                        .app_data(oracle.clone())
+                        .service(issue_token)
+                        .service(edit)
+                })
+                .workers(1)
+                .bind(("127.0.0.1", 0))
+                .unwrap();
+                sender.send(server.addrs()[0]).unwrap();
+                server.run().await
+            })
+        });
+        format!("http://{}", receiver.recv().unwrap())
+    }
+
+    #[test]
+    fn default_client_recovers_secret() {
+        let address = serve();
+        let token = reqwest::blocking::get(format!("{address}/ctr-edit/token"))
+            .unwrap()
+            .text()
+            .unwrap();
+        let message = decode_token(&token).unwrap();
+
+        let oracle = HttpEditOracle::new(&format!("{address}/ctr-edit"));
+        assert_eq!(
+            SECRET.as_bytes(),
+            recover_plain_text(&oracle, &message).unwrap()
+        );
+
+        let unlimited =
+            HttpEditOracle::new(&format!("{address}/ctr-edit")).with_max_edit_length(None);
+        assert!(unlimited.edit(&message, 0, &[0; 17]).is_err());
+    }
+}
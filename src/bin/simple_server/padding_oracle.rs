@@ -0,0 +1,50 @@
+use std::str::FromStr;
+
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse};
+use serde::Deserialize;
+
+use cryptopals::cyphers::aes::oracles::padding_oracle::{PaddingOracle, SamplePaddingOracle};
+use cryptopals::encoding::hex::Hex;
+use cryptopals::encoding::Digest;
+
+const BLOCK_SIZE: usize = 16;
+
+#[derive(Deserialize)]
+pub(crate) struct Token {
+    token: String,
+}
+
+/// Hands out a token (hex encoded iv followed by the cipher text) to attack
+#[get("/padding-oracle/token")]
+pub(crate) async fn issue_token(oracle: Data<SamplePaddingOracle>) -> HttpResponse {
+    let encrypted = oracle.encrypt_rand();
+    let token = Hex::new(&[&encrypted.iv[..], &encrypted.cipher_text].concat());
+    HttpResponse::Ok().body(token.to_string())
+}
+
+/// Decrypts a token, leaking whether its padding is valid through the status code
+///
+/// 200 if the padding is valid, 403 if it is not and 400 if the token is not hex or is not a whole
+/// number of blocks after the iv
+#[get("/padding-oracle")]
+pub(crate) async fn check_token(
+    token: Query<Token>,
+    oracle: Data<SamplePaddingOracle>,
+) -> HttpResponse {
+    let token = match Hex::from_str(&token.token) {
+        Ok(token) => token,
+        Err(_) => return HttpResponse::BadRequest().body("token is not valid hex"),
+    };
+    let token = token.bytes();
+    if token.len() < 2 * BLOCK_SIZE || !token.len().is_multiple_of(BLOCK_SIZE) {
+        return HttpResponse::BadRequest().body("token is not a whole number of blocks");
+    }
+
+    let (iv, cipher_text) = token.split_at(BLOCK_SIZE);
+    match oracle.is_valid_padding(cipher_text, iv) {
+        Ok(true) => HttpResponse::Ok().body("Welcome back!"),
+        Ok(false) => HttpResponse::Forbidden().body("invalid padding"),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
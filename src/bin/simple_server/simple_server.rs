@@ -2,17 +2,26 @@ use actix_session::storage::CookieSessionStore;
 use actix_session::SessionMiddleware;
 use actix_web::cookie::Key;
 use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use rand::RngCore;
+
+use cryptopals::cyphers::aes::ctr::CTRSampleEncryptions;
+use cryptopals::cyphers::aes::oracles::padding_oracle::SamplePaddingOracle;
 
 pub mod challenge_34;
+pub mod ctr_edit;
+pub mod padding_oracle;
 pub mod timing_attack;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let key = {
         let mut bytes = [0; 16];
-        openssl::rand::rand_bytes(&mut bytes).expect("random key generation failed");
+        rand::thread_rng().fill_bytes(&mut bytes);
         bytes
     };
+    // created once so every worker shares the same key
+    let padding_oracle = web::Data::new(SamplePaddingOracle::new());
+    let ctr_edit_oracle = web::Data::new(CTRSampleEncryptions::new());
 
     HttpServer::new(move || {
         App::new()
@@ -21,6 +30,8 @@ async fn main() -> std::io::Result<()> {
                 Key::generate(),
             ))
             .app_data(web::Data::new(key))
+            .app_data(padding_oracle.clone())
+            .app_data(ctr_edit_oracle.clone())
             .service(hello)
             .service(echo)
             .service(timing_attack::receive_secure_thing)
@@ -29,6 +40,10 @@ async fn main() -> std::io::Result<()> {
             .service(challenge_34::exchange_message)
             .service(challenge_34::exchange_keys_mitm)
             .service(challenge_34::exchange_message_mitm)
+            .service(padding_oracle::issue_token)
+            .service(padding_oracle::check_token)
+            .service(ctr_edit::issue_token)
+            .service(ctr_edit::edit)
             .route("/hey", web::get().to(manual_hello))
     })
     .bind(("127.0.0.1", 8080))?
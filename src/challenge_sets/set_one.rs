@@ -1,9 +1,7 @@
 use std::fs;
 use std::str::FromStr;
 
-use openssl::symm;
-use openssl::symm::Cipher;
-
+use cryptopals::cyphers::aes::ecb;
 use cryptopals::cyphers::{caesar_cypher, vigenere};
 use cryptopals::encoding::base64::Base64;
 use cryptopals::encoding::hex::Hex;
@@ -128,10 +126,8 @@ fn challenge_six() {
 fn challenge_seven() {
     let encrypted_message = Base64::from_file("7.txt").expect("failed to read file");
     let key = "YELLOW SUBMARINE".as_bytes();
-    let cipher = Cipher::aes_128_ecb();
 
-    let decrypted_message =
-        symm::decrypt(cipher, key, None, encrypted_message.bytes()).expect("decryption failed");
+    let decrypted_message = ecb::decrypt(key, encrypted_message);
 
     println!(
         "The message is: {}",
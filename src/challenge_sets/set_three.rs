@@ -2,8 +2,7 @@ use std::str::FromStr;
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use openssl::rand as ssl_rand;
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 use cryptopals::cyphers::aes::ctr;
 use cryptopals::cyphers::aes::ctr::{CTRSampleEncryptions, EncryptedMessage};
@@ -53,7 +52,7 @@ fn challenge_seventeen() {
     for _ in 0..=1 {
         let oracle = SamplePaddingOracle::new();
         let encryption = oracle.encrypt_rand();
-        let decrypted = oracle.decrypt(&encryption);
+        let decrypted = oracle.decrypt(&encryption).unwrap();
         println!("result: {}", String::from_utf8_lossy(&decrypted));
     }
 }
@@ -214,7 +213,7 @@ fn challenge_twenty_four() {
 
     let len = rand::thread_rng().gen_range(20..40);
     let mut rand_bytes = [0; 40];
-    ssl_rand::rand_bytes(&mut rand_bytes).expect("ssl random_bytes() failed");
+    rand::thread_rng().fill_bytes(&mut rand_bytes);
     let message = {
         let message = rand_bytes[..len].to_vec();
         let message = String::from_utf8_lossy(&message);
@@ -1,12 +1,14 @@
 use cryptopals::cyphers::aes::cbc;
+use cryptopals::cyphers::aes::oracles::bit_flipping::{self, Layout};
 use cryptopals::cyphers::aes::oracles::cbc_oracle::CBCOracle;
-use cryptopals::cyphers::aes::oracles::ecb_oracle;
 use cryptopals::cyphers::aes::oracles::ecb_oracle::{
     BasicECBOracle, ECBOracle, RandomPrefixECBOracle,
 };
+use cryptopals::cyphers::aes::oracles::{ecb_cut_paste, ecb_oracle};
 use cryptopals::cyphers::aes::AesMode;
 use cryptopals::cyphers::padding::pkcs7;
 use cryptopals::encoding::base64::Base64;
+use cryptopals::encoding::structured_cookie::StructuredCookie;
 use cryptopals::encoding::Digest;
 use cryptopals::profile::{Profile, ProfileEncrypter};
 
@@ -82,6 +84,11 @@ fn challenge_eleven() {
             AesMode::CBC => println!("CBC encryption used"),
             AesMode::ECB => println!("ECB encryption used"),
             AesMode::CTR => println!("CTR encryption used"),
+            AesMode::GCM => println!("GCM encryption used"),
+            AesMode::CFB => println!("CFB encryption used"),
+            AesMode::CFB8 => println!("CFB-8 encryption used"),
+            AesMode::OFB => println!("OFB encryption used"),
+            AesMode::PCBC => println!("PCBC encryption used"),
         };
     })
 }
@@ -89,10 +96,10 @@ fn challenge_eleven() {
 /// https://cryptopals.com/sets/2/challenges/12
 fn challenge_twelve() {
     let oracle = BasicECBOracle::new();
-    let key_length = oracle.find_key_length().expect("key length > 128");
+    let key_length = oracle.find_key_length().expect("not a block cipher");
     println!("key length is {}", key_length);
 
-    let decrypted_message = oracle.decrypt();
+    let decrypted_message = oracle.decrypt().unwrap();
 
     print!(
         "The message is: \n{}\n",
@@ -102,65 +109,27 @@ fn challenge_twelve() {
 
 ///https://cryptopals.com/sets/2/challenges/13
 fn challenge_thirteen() {
-    let target_email = "sam.rosenberg@secret.com".to_string();
+    let target_email = "sam.rosenberg@secret.com";
+    let profile_encrypter = ProfileEncrypter::new();
 
-    let oracle = ProfileOracle {
-        profile_encrypter: ProfileEncrypter::new(),
-        target_email,
+    let encrypt = |email: &str| {
+        profile_encrypter.encrypt(&Profile::profile_for(&format!("{target_email}{email}")))
     };
-    let key_length = oracle.find_key_length().expect("key length > 128");
-
-    // with email=target_email at the start of a string we may need to push extra bytes
-    // to ensure that the "admin..." string is encoded as a single block
-    let mut padded_admin = "A"
-        .repeat(key_length - ((oracle.target_email.len() + 6) % key_length))
-        .into_bytes();
-    padded_admin.extend_from_slice(&pkcs7::pad("admin".as_bytes(), key_length));
-    let encrypted_admin = oracle.encrypt(padded_admin);
-
-    let byte_remainder = (0..key_length).find(|i| {
-        let encrypted_a = oracle.encrypt(&"A".repeat(*i).into_bytes());
-        let encrypted_b = oracle.encrypt(&"A".repeat(i + 1).into_bytes());
-        encrypted_b.len() > encrypted_a.len()
-    });
-
-    let target_remainder = {
-        match byte_remainder {
-            Some(remainder) => key_length + 5 - remainder,
-            None => 5,
-        }
+    let decrypt = |cipher_text: &[u8]| {
+        profile_encrypter
+            .try_decrypt(cipher_text)
+            .map(StructuredCookie::from)
     };
+    let forged = ecb_cut_paste::forge(encrypt, decrypt, "role", "admin").unwrap();
 
-    let mut trojan_encrypted = oracle.encrypt(&"A".repeat(target_remainder).into_bytes());
-    trojan_encrypted.truncate(trojan_encrypted.len() - 16);
-
-    trojan_encrypted.extend_from_slice(&encrypted_admin[key_length * 2..key_length * 3]);
-
-    println!(
-        "decrypted is: {}",
-        oracle.profile_encrypter.decrypt(&trojan_encrypted)
-    );
-}
-
-struct ProfileOracle {
-    profile_encrypter: ProfileEncrypter,
-    target_email: String,
-}
-
-impl ECBOracle for ProfileOracle {
-    fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
-        let mut known_bytes = self.target_email.clone().into_bytes();
-        known_bytes.extend_from_slice(message.bytes());
-        let profile = Profile::profile_for(&String::from_utf8(known_bytes).unwrap());
-        self.profile_encrypter.encrypt(&profile)
-    }
+    println!("decrypted is: {}", profile_encrypter.decrypt(&forged));
 }
 
 /// https://cryptopals.com/sets/2/challenges/14
 fn challenge_fourteen() {
     let oracle = RandomPrefixECBOracle::new();
 
-    let decrypted_message = oracle.decrypt();
+    let decrypted_message = oracle.decrypt().unwrap();
     print!(
         "The message is: \n{}\n",
         String::from_utf8_lossy(&decrypted_message)
@@ -186,21 +155,16 @@ fn challenge_sixteen() {
     let oracle = CBCOracle::new();
     let attack_text = "this comment is exact:admin?true".as_bytes().to_vec();
 
-    let mut cipher_text = oracle.encrypt_with_message(&attack_text).cipher_text;
-
-    let semi_colon_mask = 0b_01;
-    let equal_sign_mask = 0b_10;
+    let cipher_text = oracle.encrypt_with_message(&attack_text).cipher_text;
 
-    // Based on the structure of the prepended text and the attack text encrypted
-    // these are the positions 1 block before/1 block after the characters I'm trying to change
-    // positions 4; 10; 15
-    let positions = vec![(37, semi_colon_mask), (43, equal_sign_mask)];
-
-    positions.into_iter().for_each(|(pos, mask)| {
-        let target = cipher_text.remove(pos);
-        let target = target ^ mask;
-        cipher_text.insert(pos, target);
-    });
+    // the first block of the attack text is garbled to change ":admin?true" in the second
+    let layout = Layout {
+        prefix_length: "comment1=cooking%20MCs;userdata=".len(),
+        offset: 21,
+        known: ":admin?true".as_bytes().to_vec(),
+        desired: ";admin=true".as_bytes().to_vec(),
+    };
+    let cipher_text = bit_flipping::flip_cbc(&cipher_text, 16, &layout).unwrap();
 
     assert!(oracle.is_admin(&cipher_text));
 }
@@ -1,5 +1,9 @@
 pub mod aes;
+pub mod block_cipher;
 pub mod caesar_cypher;
+pub mod feistel;
+pub mod gf128;
 pub mod mersenne_twister;
+pub mod modes;
 pub mod padding;
 pub mod vigenere;
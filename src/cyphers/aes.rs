@@ -1,26 +1,37 @@
-use openssl::rand;
+use rand::RngCore;
 
 pub mod cbc;
+pub mod cfb;
+pub mod cipher;
 pub mod ctr;
 pub mod ecb;
+pub mod gcm;
+pub mod ofb;
 pub mod oracles;
+pub mod pcbc;
+pub mod xts;
 
 #[derive(Debug, PartialEq)]
 pub enum AesMode {
     ECB,
     CBC,
     CTR,
+    GCM,
+    CFB,
+    CFB8,
+    OFB,
+    PCBC,
 }
 
 pub fn get_random_bytes(length: usize) -> Vec<u8> {
     let mut key = vec![0; length];
-    rand::rand_bytes(&mut key).expect("key generation failed");
+    rand::thread_rng().fill_bytes(&mut key);
     key
 }
 
 pub fn generate_16_bit_key() -> [u8; 16] {
     let mut key = [0; 16];
-    rand::rand_bytes(&mut key).expect("key generation failed");
+    rand::thread_rng().fill_bytes(&mut key);
     key
 }
 
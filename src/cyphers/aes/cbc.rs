@@ -1,40 +1,203 @@
-use crate::cyphers::aes::ecb;
-use crate::cyphers::padding::pkcs7;
+use std::io::{self, Read, Write};
 
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::modes::cbc::{Cbc, CbcDecryptReader, CbcEncryptWriter, CiphertextStealing};
+use crate::Error;
+
+/// Pads the plain text using pkcs#7 and encrypts it with AES in CBC mode
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
 pub fn encrypt(plain_text: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
-    let mut last_block = iv.to_vec();
-    let plain_text = pkcs7::pad(plain_text, key.len());
-    plain_text
-        .chunks(key.len())
-        .flat_map(|chunk| {
-            let xor_block: Vec<u8> = chunk.iter().zip(&last_block).map(|(x, y)| x ^ y).collect();
-            let encrypted_message = ecb::encrypt_block(key, &xor_block);
-            last_block = encrypted_message.clone();
-            encrypted_message
-        })
-        .collect()
+    Cbc::new(Aes::new(key).expect("invalid aes key")).encrypt(plain_text, iv)
+}
+
+/// Decrypts an AES CBC cipher text; padding is left in place
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or the cipher text is not a whole number of blocks
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn try_decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+    Cbc::new(Aes::new(key)?).decrypt(encrypted_message, iv)
 }
 
+/// Decrypts an AES CBC cipher text; padding is left in place
+///
+/// Panics
+/// If decryption fails for any reason, or the iv is not 16 bytes long
 pub fn decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
-    let mut last_block = iv;
-    encrypted_message
-        .chunks(16)
-        .flat_map(|chunk| {
-            let next_block = decrypt_block(key, last_block, chunk);
-            last_block = chunk;
-            next_block
-        })
-        .collect()
-}
-
-fn decrypt_block(key: &[u8], last_block: &[u8], chunk: &[u8]) -> Vec<u8> {
-    let decrypted_chunk = ecb::decrypt_block(key, chunk);
-
-    decrypted_chunk
-        .iter()
-        // pad with zero if less than 16 bytes; zip will cut off any excess padding
-        .chain([0b0; 16].iter())
-        .zip(last_block.iter())
-        .map(|(x, y)| x ^ y)
-        .collect()
+    try_decrypt(encrypted_message, key, iv).expect("decryption failed")
+}
+
+/// Encrypts the plain text with AES in CBC mode using ciphertext stealing instead of padding
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or the plain text is shorter than 16 bytes
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn encrypt_cts(
+    plain_text: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    variant: CiphertextStealing,
+) -> Result<Vec<u8>, Error> {
+    Cbc::new(Aes::new(key)?).encrypt_cts(plain_text, iv, variant)
+}
+
+/// Decrypts an AES CBC cipher text encrypted with ciphertext stealing
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or the cipher text is shorter than 16 bytes
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn decrypt_cts(
+    encrypted_message: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    variant: CiphertextStealing,
+) -> Result<Vec<u8>, Error> {
+    Cbc::new(Aes::new(key)?).decrypt_cts(encrypted_message, iv, variant)
+}
+
+/// Encrypts everything read from the reader with AES in CBC mode, padding with pkcs#7, and writes
+/// the cipher text to the writer without holding the whole message in memory
+///
+/// Returns the number of plain text bytes encrypted
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or if reading or writing fails
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn encrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: W,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<u64, Error> {
+    let mut encrypter = CbcEncryptWriter::new(Aes::new(key)?, iv, writer);
+    let copied = io::copy(reader, &mut encrypter)?;
+    encrypter.finish()?;
+    Ok(copied)
+}
+
+/// Decrypts an AES CBC cipher text read from the reader and writes the plain text to the writer;
+/// padding is left in place
+///
+/// Returns the number of bytes written
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or if reading or writing fails
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn decrypt_stream<R: Read, W: Write>(
+    reader: R,
+    writer: &mut W,
+    key: &[u8],
+    iv: &[u8],
+) -> Result<u64, Error> {
+    let mut decrypter = CbcDecryptReader::new(Aes::new(key)?, iv, reader);
+    Ok(io::copy(&mut decrypter, writer)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::cbc::{
+        decrypt, decrypt_cts, decrypt_stream, encrypt, encrypt_cts, encrypt_stream, try_decrypt,
+    };
+    use crate::cyphers::modes::cbc::CiphertextStealing;
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        Hex::from_str(hex).unwrap().bytes().to_vec()
+    }
+
+    #[test]
+    fn stream_round_trip_matches_slices() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [0; 16];
+        let plain_text = "a capture file too large to hold in memory twice".repeat(100);
+
+        let mut encrypted = Vec::new();
+        encrypt_stream(&mut plain_text.as_bytes(), &mut encrypted, key, &iv).unwrap();
+        assert_eq!(encrypt(plain_text.as_bytes(), key, &iv), encrypted);
+
+        let mut decrypted = Vec::new();
+        decrypt_stream(encrypted.as_slice(), &mut decrypted, key, &iv).unwrap();
+        assert_eq!(decrypt(&encrypted, key, &iv), decrypted);
+    }
+
+    #[test]
+    fn truncated_cipher_text_is_rejected() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [0; 16];
+        let encrypted = encrypt("some message".as_bytes(), key, &iv);
+
+        assert!(try_decrypt(&encrypted[..15], key, &iv).is_err());
+    }
+
+    /// The AES test vectors of RFC 3962, which uses CBC-CS3
+    #[test]
+    fn ciphertext_stealing_matches_rfc3962_vectors() {
+        let key = "chicken teriyaki".as_bytes();
+        let iv = [0; 16];
+        let vectors = [
+            (
+                "4920776f756c64206c696b652074686520",
+                "c6353568f2bf8cb4d8a580362da7ff7f97",
+            ),
+            (
+                "4920776f756c64206c696b65207468652047656e6572616c20476175277320",
+                "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+            ),
+            (
+                "4920776f756c64206c696b65207468652047656e6572616c2047617527732043",
+                "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+            ),
+        ];
+
+        for (plain_text, cipher_text) in vectors {
+            let (plain_text, cipher_text) = (bytes(plain_text), bytes(cipher_text));
+            assert_eq!(
+                cipher_text,
+                encrypt_cts(&plain_text, key, &iv, CiphertextStealing::CS3).unwrap()
+            );
+            assert_eq!(
+                plain_text,
+                decrypt_cts(&cipher_text, key, &iv, CiphertextStealing::CS3).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn ciphertext_stealing_variants_order_final_blocks() {
+        let key = "chicken teriyaki".as_bytes();
+        let iv = [0; 16];
+        let partial = bytes("4920776f756c64206c696b652074686520");
+        let whole = bytes("4920776f756c64206c696b65207468652047656e6572616c2047617527732043");
+
+        assert_eq!(
+            bytes("97c6353568f2bf8cb4d8a580362da7ff7f"),
+            encrypt_cts(&partial, key, &iv, CiphertextStealing::CS1).unwrap()
+        );
+        assert_eq!(
+            bytes("c6353568f2bf8cb4d8a580362da7ff7f97"),
+            encrypt_cts(&partial, key, &iv, CiphertextStealing::CS2).unwrap()
+        );
+        // whole blocks are left in cbc order by cs1 and cs2
+        for variant in [CiphertextStealing::CS1, CiphertextStealing::CS2] {
+            assert_eq!(
+                encrypt(&whole, key, &iv)[..32],
+                encrypt_cts(&whole, key, &iv, variant).unwrap()
+            );
+        }
+    }
 }
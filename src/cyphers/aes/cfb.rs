@@ -0,0 +1,77 @@
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::modes::cfb::Cfb;
+
+/// Encrypts the plain text with AES in full-block (128 bit) CFB mode; no padding is added
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn encrypt(plain_text: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Cfb::new(Aes::new(key).expect("invalid aes key")).encrypt(plain_text, iv)
+}
+
+/// Decrypts an AES full-block (128 bit) CFB cipher text
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Cfb::new(Aes::new(key).expect("invalid aes key")).decrypt(encrypted_message, iv)
+}
+
+/// Encrypts the plain text with AES in CFB-8 mode, one byte per block encryption
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn encrypt_cfb8(plain_text: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Cfb::with_segment_size(Aes::new(key).expect("invalid aes key"), 1).encrypt(plain_text, iv)
+}
+
+/// Decrypts an AES CFB-8 cipher text
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn decrypt_cfb8(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Cfb::with_segment_size(Aes::new(key).expect("invalid aes key"), 1)
+        .decrypt(encrypted_message, iv)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::cfb::{decrypt, decrypt_cfb8, encrypt, encrypt_cfb8};
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    const KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const IV: &str = "000102030405060708090a0b0c0d0e0f";
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        Hex::from_str(hex).unwrap().bytes().to_vec()
+    }
+
+    /// F.3.13 and F.3.14 of NIST SP 800-38A
+    #[test]
+    fn cfb128_matches_nist_vectors() {
+        let plain_text = bytes("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let cipher_text = bytes("3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6");
+
+        assert_eq!(cipher_text, encrypt(&plain_text, &bytes(KEY), &bytes(IV)));
+        assert_eq!(plain_text, decrypt(&cipher_text, &bytes(KEY), &bytes(IV)));
+    }
+
+    /// F.3.7 and F.3.8 of NIST SP 800-38A
+    #[test]
+    fn cfb8_matches_nist_vectors() {
+        let plain_text = bytes("6bc1bee22e409f96e93d7e117393172aae2d");
+        let cipher_text = bytes("3b79424c9c0dd436bace9e0ed4586a4f32b9");
+
+        assert_eq!(
+            cipher_text,
+            encrypt_cfb8(&plain_text, &bytes(KEY), &bytes(IV))
+        );
+        assert_eq!(
+            plain_text,
+            decrypt_cfb8(&cipher_text, &bytes(KEY), &bytes(IV))
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::Error;
+
+pub const BLOCK_SIZE: usize = 16;
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+const ROUND_CONSTANTS: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+#[derive(Debug, PartialEq)]
+enum AesError {
+    InvalidKeyLength(usize),
+}
+
+impl Display for AesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AesError::InvalidKeyLength(len) => {
+                write!(f, "invalid key length {len}; expected 16, 24 or 32 bytes")
+            }
+        }
+    }
+}
+
+impl StdError for AesError {}
+
+/// The AES block cipher (FIPS-197) with an expanded key schedule
+///
+/// The key length selects the variant: 16 bytes for AES-128, 24 for AES-192 and 32 for AES-256.
+/// Expanding the key is the expensive part of setup, so hold on to an Aes rather than
+/// rebuilding it for every block.
+#[derive(Debug, Clone)]
+pub struct Aes {
+    round_keys: Vec<[u8; BLOCK_SIZE]>,
+}
+
+impl Aes {
+    /// Expands the supplied key into the round keys
+    ///
+    /// Errors
+    /// If the key is not 16, 24 or 32 bytes long
+    pub fn new(key: &[u8]) -> Result<Aes, Error> {
+        match key.len() {
+            16 | 24 | 32 => Ok(Aes {
+                round_keys: expand_key(key),
+            }),
+            len => Err(Box::new(AesError::InvalidKeyLength(len))),
+        }
+    }
+
+    /// The number of rounds applied to each block: 10, 12 or 14 depending on the key length
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    pub fn encrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let rounds = self.rounds();
+
+        add_round_key(block, &self.round_keys[0]);
+        for round_key in &self.round_keys[1..rounds] {
+            sub_bytes(block);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, round_key);
+        }
+        sub_bytes(block);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[rounds]);
+    }
+
+    pub fn decrypt_block(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let rounds = self.rounds();
+
+        add_round_key(block, &self.round_keys[rounds]);
+        for round_key in self.round_keys[1..rounds].iter().rev() {
+            inv_shift_rows(block);
+            inv_sub_bytes(block);
+            add_round_key(block, round_key);
+            inv_mix_columns(block);
+        }
+        inv_shift_rows(block);
+        inv_sub_bytes(block);
+        add_round_key(block, &self.round_keys[0]);
+    }
+}
+
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let state: &mut [u8; BLOCK_SIZE] = block.try_into().expect("aes blocks are 16 bytes");
+        Aes::encrypt_block(self, state);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let state: &mut [u8; BLOCK_SIZE] = block.try_into().expect("aes blocks are 16 bytes");
+        Aes::decrypt_block(self, state);
+    }
+}
+
+/// Expands a 16, 24 or 32 byte key into rounds + 1 round keys
+///
+/// The schedule is built a word (4 bytes) at a time as described in section 5.2 of FIPS-197
+fn expand_key(key: &[u8]) -> Vec<[u8; BLOCK_SIZE]> {
+    let key_words = key.len() / 4;
+    let rounds = key_words + 6;
+    let total_words = 4 * (rounds + 1);
+
+    let mut words: Vec<[u8; 4]> = key
+        .chunks(4)
+        .map(|word| word.try_into().expect("key length is a multiple of 4"))
+        .collect();
+
+    for i in key_words..total_words {
+        let mut word = words[i - 1];
+        if i % key_words == 0 {
+            word.rotate_left(1);
+            word.iter_mut()
+                .for_each(|byte| *byte = SBOX[*byte as usize]);
+            word[0] ^= ROUND_CONSTANTS[i / key_words - 1];
+        } else if key_words > 6 && i % key_words == 4 {
+            word.iter_mut()
+                .for_each(|byte| *byte = SBOX[*byte as usize]);
+        }
+
+        let previous = words[i - key_words];
+        words.push([
+            word[0] ^ previous[0],
+            word[1] ^ previous[1],
+            word[2] ^ previous[2],
+            word[3] ^ previous[3],
+        ]);
+    }
+
+    words
+        .chunks(4)
+        .map(|round_words| {
+            let mut round_key = [0; BLOCK_SIZE];
+            round_key.copy_from_slice(&round_words.concat());
+            round_key
+        })
+        .collect()
+}
+
+fn add_round_key(state: &mut [u8; BLOCK_SIZE], round_key: &[u8; BLOCK_SIZE]) {
+    state
+        .iter_mut()
+        .zip(round_key.iter())
+        .for_each(|(x, y)| *x ^= y);
+}
+
+fn sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    state
+        .iter_mut()
+        .for_each(|byte| *byte = SBOX[*byte as usize]);
+}
+
+fn inv_sub_bytes(state: &mut [u8; BLOCK_SIZE]) {
+    state
+        .iter_mut()
+        .for_each(|byte| *byte = INV_SBOX[*byte as usize]);
+}
+
+// The state is stored column by column, so the byte at row r, column c lives at index r + 4c
+fn shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let original = *state;
+    for row in 1..4 {
+        for column in 0..4 {
+            state[row + 4 * column] = original[row + 4 * ((column + row) % 4)];
+        }
+    }
+}
+
+fn inv_shift_rows(state: &mut [u8; BLOCK_SIZE]) {
+    let original = *state;
+    for row in 1..4 {
+        for column in 0..4 {
+            state[row + 4 * ((column + row) % 4)] = original[row + 4 * column];
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    state.chunks_mut(4).for_each(|column| {
+        let [a, b, c, d] = [column[0], column[1], column[2], column[3]];
+        column[0] = gf_multiply(a, 2) ^ gf_multiply(b, 3) ^ c ^ d;
+        column[1] = a ^ gf_multiply(b, 2) ^ gf_multiply(c, 3) ^ d;
+        column[2] = a ^ b ^ gf_multiply(c, 2) ^ gf_multiply(d, 3);
+        column[3] = gf_multiply(a, 3) ^ b ^ c ^ gf_multiply(d, 2);
+    });
+}
+
+fn inv_mix_columns(state: &mut [u8; BLOCK_SIZE]) {
+    state.chunks_mut(4).for_each(|column| {
+        let [a, b, c, d] = [column[0], column[1], column[2], column[3]];
+        column[0] =
+            gf_multiply(a, 14) ^ gf_multiply(b, 11) ^ gf_multiply(c, 13) ^ gf_multiply(d, 9);
+        column[1] =
+            gf_multiply(a, 9) ^ gf_multiply(b, 14) ^ gf_multiply(c, 11) ^ gf_multiply(d, 13);
+        column[2] =
+            gf_multiply(a, 13) ^ gf_multiply(b, 9) ^ gf_multiply(c, 14) ^ gf_multiply(d, 11);
+        column[3] =
+            gf_multiply(a, 11) ^ gf_multiply(b, 13) ^ gf_multiply(c, 9) ^ gf_multiply(d, 14);
+    });
+}
+
+/// Multiplies two elements of GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1
+fn gf_multiply(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let high_bit_set = a & 0x80 != 0;
+        a <<= 1;
+        if high_bit_set {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::cipher::{Aes, BLOCK_SIZE};
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    const PLAIN_TEXT: &str = "00112233445566778899aabbccddeeff";
+
+    fn fips_197_round_trip(key: &str, expected_cipher_text: &str) {
+        let key = Hex::from_str(key).unwrap();
+        let aes = Aes::new(key.bytes()).unwrap();
+        let plain_text: [u8; BLOCK_SIZE] = Hex::from_str(PLAIN_TEXT)
+            .unwrap()
+            .bytes()
+            .try_into()
+            .unwrap();
+
+        let mut block = plain_text;
+        aes.encrypt_block(&mut block);
+        assert_eq!(expected_cipher_text, Hex::new(&block).to_string());
+
+        aes.decrypt_block(&mut block);
+        assert_eq!(plain_text, block);
+    }
+
+    // FIPS-197 Appendix C.1
+    #[test]
+    fn aes_128_matches_fips_197() {
+        fips_197_round_trip(
+            "000102030405060708090a0b0c0d0e0f",
+            "69c4e0d86a7b0430d8cdb78070b4c55a",
+        );
+    }
+
+    // FIPS-197 Appendix C.2
+    #[test]
+    fn aes_192_matches_fips_197() {
+        fips_197_round_trip(
+            "000102030405060708090a0b0c0d0e0f1011121314151617",
+            "dda97ca4864cdfe06eaf70a0ec0d7191",
+        );
+    }
+
+    // FIPS-197 Appendix C.3
+    #[test]
+    fn aes_256_matches_fips_197() {
+        fips_197_round_trip(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+            "8ea2b7ca516745bfeafc49904b496089",
+        );
+    }
+
+    // FIPS-197 Appendix A.1
+    #[test]
+    fn key_expansion_matches_fips_197() {
+        let key = Hex::from_str("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let aes = Aes::new(key.bytes()).unwrap();
+
+        assert_eq!(10, aes.rounds());
+        assert_eq!(
+            "d014f9a8c9ee2589e13f0cc8b6630ca6",
+            Hex::new(&aes.round_keys[10]).to_string()
+        );
+    }
+
+    #[test]
+    fn invalid_key_length_is_rejected() {
+        assert!(Aes::new(&[0; 15]).is_err());
+        assert!(Aes::new(&[0; 20]).is_err());
+    }
+
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn matches_openssl() {
+        use openssl::symm::{self, Cipher};
+
+        use crate::cyphers::aes::get_random_bytes;
+
+        let ciphers = [
+            (16, Cipher::aes_128_ecb()),
+            (24, Cipher::aes_192_ecb()),
+            (32, Cipher::aes_256_ecb()),
+        ];
+
+        for (key_length, cipher) in ciphers {
+            let key = get_random_bytes(key_length);
+            let plain_text = get_random_bytes(BLOCK_SIZE * 8);
+            let aes = Aes::new(&key).unwrap();
+
+            let native: Vec<u8> = plain_text
+                .chunks(BLOCK_SIZE)
+                .flat_map(|chunk| {
+                    let mut block: [u8; BLOCK_SIZE] = chunk.try_into().unwrap();
+                    aes.encrypt_block(&mut block);
+                    block
+                })
+                .collect();
+            let mut openssl = symm::encrypt(cipher, &key, None, &plain_text).unwrap();
+            // openssl always appends a block of pkcs#7 padding
+            openssl.truncate(plain_text.len());
+
+            assert_eq!(openssl, native);
+        }
+    }
+}
@@ -1,8 +1,18 @@
-use crate::cyphers::aes::{ecb, get_random_bytes};
+use std::io::{self, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::aes::get_random_bytes;
+use crate::cyphers::modes::ctr::{Ctr, CtrStream};
 use crate::encoding::base64::Base64;
 use crate::encoding::Digest;
+use crate::Error;
 
-const BLOCK_SIZE: usize = 16;
+/// Inputs at least this long are encrypted in parallel when the parallel feature is enabled
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 64 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct EncryptedMessage {
@@ -11,30 +21,53 @@ pub struct EncryptedMessage {
 }
 
 pub fn encrypt<T: Digest>(plain_text: &T, key: &[u8], nonce: u64) -> EncryptedMessage {
-    let cipher_text = plain_text
-        .bytes()
-        .chunks(BLOCK_SIZE)
-        .enumerate()
-        .flat_map(|(count, block)| {
-            let encrypted = block
-                .iter()
-                .zip(ecb::encrypt(
-                    key,
-                    [&nonce.to_le_bytes(), &count.to_le_bytes()[..]].concat(),
-                ))
-                .map(|(x, y)| x ^ y)
-                .collect::<Vec<u8>>();
-            encrypted
-        })
-        .collect();
+    let ctr = Ctr::new(Aes::new(key).expect("invalid aes key"));
+    let cipher_text = apply_keystream(&ctr, plain_text.bytes(), nonce);
 
     EncryptedMessage { cipher_text, nonce }
 }
 
+/// Encrypts every message under the same key and nonce, expanding the key only once
+///
+/// With the parallel feature enabled the messages are encrypted concurrently
+pub fn encrypt_all<T: Digest + Sync>(
+    plain_texts: &[T],
+    key: &[u8],
+    nonce: u64,
+) -> Vec<EncryptedMessage> {
+    let ctr = Ctr::new(Aes::new(key).expect("invalid aes key"));
+    let encrypt = |plain_text: &T| EncryptedMessage {
+        cipher_text: apply_keystream(&ctr, plain_text.bytes(), nonce),
+        nonce,
+    };
+
+    #[cfg(feature = "parallel")]
+    return plain_texts.par_iter().map(encrypt).collect();
+    #[cfg(not(feature = "parallel"))]
+    plain_texts.iter().map(encrypt).collect()
+}
+
 pub fn decrypt(cipher_text: &EncryptedMessage, key: &[u8]) -> Vec<u8> {
     encrypt(&cipher_text.cipher_text, key, cipher_text.nonce).cipher_text
 }
 
+/// Applies the AES CTR key stream to everything read from the reader and writes the result to the
+/// writer; encryption and decryption are the same operation
+///
+/// Returns the number of bytes processed
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or if reading or writing fails
+pub fn apply_keystream_stream<R: io::Read, W: Write>(
+    reader: R,
+    writer: &mut W,
+    key: &[u8],
+    nonce: u64,
+) -> Result<u64, Error> {
+    let mut stream = CtrStream::new(Aes::new(key)?, nonce, reader);
+    Ok(io::copy(&mut stream, writer)?)
+}
+
 pub struct CTRSampleEncryptions {
     pub key: Vec<u8>,
 }
@@ -65,31 +98,17 @@ impl CTRSampleEncryptions {
         offset: usize,
         new_text: &T,
     ) -> EncryptedMessage {
-        let mut count = (offset / BLOCK_SIZE) as u64;
-        let alignment = offset % BLOCK_SIZE;
-        let aligned_text = [[0].repeat(alignment), new_text.bytes().to_vec()].concat();
-        let nonce = cipher_text.nonce.to_le_bytes();
-        let new_cipher_text: Vec<u8> = aligned_text
-            .chunks(BLOCK_SIZE)
-            .flat_map(|block| {
-                let encrypted = block
-                    .iter()
-                    .zip(ecb::encrypt(
-                        &self.key,
-                        [&nonce, &count.to_le_bytes()[..]].concat(),
-                    ))
-                    .map(|(x, y)| x ^ y)
-                    .collect::<Vec<u8>>();
-                count += 1;
-                encrypted
-            })
-            .collect();
-
-        let (_, new_cipher_text) = new_cipher_text.split_at(alignment);
+        let ctr = Ctr::new(Aes::new(&self.key).expect("invalid aes key"));
+        let mut key_stream = ctr.keystream(cipher_text.nonce);
+        key_stream.seek(offset as u64);
+        let mut new_cipher_text = new_text.bytes().to_vec();
+        key_stream
+            .apply_keystream(&mut new_cipher_text)
+            .expect("the counter wraps instead of overflowing");
 
         let (prefix, rest) = cipher_text.cipher_text.split_at(offset);
         let (_, suffix) = rest.split_at(new_text.len());
-        let new_cipher_text = [prefix, new_cipher_text, suffix].concat();
+        let new_cipher_text = [prefix, &new_cipher_text, suffix].concat();
 
         EncryptedMessage {
             cipher_text: new_cipher_text,
@@ -126,9 +145,55 @@ impl CTRSampleEncryptions {
 
     pub fn encrypt_messages_with_fixed_nonce(&self, file_path: &str) -> Vec<EncryptedMessage> {
         let messages = Base64::from_file_multi(file_path).unwrap();
-        messages
-            .into_iter()
-            .map(|plain_text| encrypt(&plain_text, &self.key, 0))
-            .collect()
+        encrypt_all(&messages, &self.key, 0)
+    }
+}
+
+/// Large inputs are split across threads when the parallel feature is enabled
+fn apply_keystream(ctr: &Ctr<Aes>, data: &[u8], nonce: u64) -> Vec<u8> {
+    #[cfg(feature = "parallel")]
+    if data.len() >= PARALLEL_THRESHOLD {
+        return ctr.par_apply_keystream(data, nonce);
+    }
+    ctr.apply_keystream(data, nonce)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::ctr::{apply_keystream_stream, encrypt, encrypt_all};
+
+    #[test]
+    fn stream_matches_slice_encryption() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plain_text = "streamed through counter mode".repeat(10).into_bytes();
+
+        let mut encrypted = Vec::new();
+        apply_keystream_stream(plain_text.as_slice(), &mut encrypted, key, 3).unwrap();
+
+        assert_eq!(encrypt(&plain_text, key, 3).cipher_text, encrypted);
+    }
+
+    #[test]
+    fn encrypt_all_matches_each_message() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        // the last message is long enough to be encrypted in parallel itself
+        let plain_texts: Vec<Vec<u8>> = ["", "a", "sixteen byte msg", "a few blocks of plain text"]
+            .iter()
+            .map(|plain_text| plain_text.as_bytes().to_vec())
+            .chain([vec![7; 64 * 1024 + 5]])
+            .collect();
+
+        let encrypted = encrypt_all(&plain_texts, key, 5);
+        assert_eq!(plain_texts.len(), encrypted.len());
+        plain_texts
+            .iter()
+            .zip(encrypted)
+            .for_each(|(plain_text, encrypted)| {
+                assert_eq!(5, encrypted.nonce);
+                assert_eq!(
+                    encrypt(plain_text, key, 5).cipher_text,
+                    encrypted.cipher_text
+                );
+            });
     }
 }
@@ -1,15 +1,14 @@
-use openssl::error::ErrorStack;
-use openssl::symm;
-use openssl::symm::{Cipher, Crypter, Mode};
-
-use crate::cyphers::padding::pkcs7;
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::modes::ecb::Ecb;
 use crate::encoding::Digest;
+use crate::Error;
 
 /// Uses a given key to decrypt a given message digest
 ///
-/// Thin wrapper around the openssl::symm::decrypt function
-pub fn try_decrypt<T: Digest>(key: &[u8], message: T) -> Result<Vec<u8>, ErrorStack> {
-    symm::decrypt(Cipher::aes_128_ecb(), key, None, message.bytes())
+/// The key may be 16, 24 or 32 bytes long. The decrypted message must end with valid pkcs#7 padding,
+/// which is removed
+pub fn try_decrypt<T: Digest>(key: &[u8], message: T) -> Result<Vec<u8>, Error> {
+    Ecb::new(Aes::new(key)?).try_decrypt(message.bytes())
 }
 
 /// Uses a given key to decrypt a given message digest
@@ -19,66 +18,42 @@ pub fn decrypt<T: Digest>(key: &[u8], message: T) -> Vec<u8> {
     try_decrypt(key, message).expect("decryption failed")
 }
 
-/// Uses a given key to decrypt a given message digest
+/// Uses a given key to encrypt a given message digest
 ///
-/// Thin wrapper around the openssl:symm:encrypt function
-pub fn try_encrypt<T: Digest>(key: &[u8], message: T) -> Result<Vec<u8>, ErrorStack> {
-    symm::encrypt(Cipher::aes_128_ecb(), key, None, message.bytes())
+/// The key may be 16, 24 or 32 bytes long. The message is padded using pkcs#7 before encryption
+pub fn try_encrypt<T: Digest>(key: &[u8], message: T) -> Result<Vec<u8>, Error> {
+    Ok(Ecb::new(Aes::new(key)?).encrypt(message.bytes()))
 }
 
 /// Uses a given key to encrypt a given message digest
 ///
 /// Panics if a encryption fails for any reason
 pub fn encrypt<T: Digest>(key: &[u8], message: T) -> Vec<u8> {
-    try_encrypt(key, message).expect("decryption failed")
+    try_encrypt(key, message).expect("encryption failed")
 }
 
-pub(crate) fn decrypt_block(key: &[u8], block: &[u8]) -> Vec<u8> {
-    let cipher = Cipher::aes_128_ecb();
-    let mut decrypter =
-        Crypter::new(cipher, Mode::Decrypt, key, None).expect("openssl failed to build Crypter");
-
-    // padding will be handled manually;
-    decrypter.pad(false);
-
-    let block_size = cipher.block_size();
-    let input = pkcs7::pad(block, block_size);
-
-    let mut decrypted_block = vec![0; input.len() + block_size];
-    decrypter
-        .update(&input, &mut decrypted_block)
-        .expect("decryption update failed");
-
-    decrypter
-        .finalize(&mut decrypted_block[..])
-        .expect("decryption finalize failed");
-
-    decrypted_block.truncate(block_size);
-    decrypted_block
-}
-
-pub(crate) fn encrypt_block(key: &[u8], block: &[u8]) -> Vec<u8> {
-    let cipher = Cipher::aes_128_ecb();
-
-    let mut encrypter =
-        Crypter::new(cipher, Mode::Encrypt, key, None).expect("openssl failed to build Crypter");
-
-    // padding will be handled manually;
-    encrypter.pad(false);
-
-    let block_size = cipher.block_size();
-    let input = pkcs7::pad(block, block_size);
-
-    let mut encrypted_block = vec![0; input.len() + block_size];
-
-    encrypter
-        .update(&input, &mut encrypted_block)
-        .expect("encryption update failed");
-
-    encrypter
-        .finalize(&mut encrypted_block[..])
-        .expect("encryption finalize failed");
-
-    encrypted_block.truncate(block_size);
-    encrypted_block
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::ecb::{decrypt, encrypt, try_decrypt};
+
+    #[test]
+    fn round_trip_encryption() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plain_text = "ecb with all three key lengths".as_bytes().to_vec();
+
+        for key in [key.to_vec(), key[..8].repeat(3), key.repeat(2)] {
+            let encrypted = encrypt(&key, &plain_text);
+            assert_eq!(32, encrypted.len());
+            assert_eq!(plain_text, decrypt(&key, encrypted));
+        }
+    }
+
+    #[test]
+    fn truncated_cipher_text_is_rejected() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let mut encrypted = encrypt(key, "some message".as_bytes().to_vec());
+        encrypted.pop();
+
+        assert!(try_decrypt(key, encrypted).is_err());
+    }
 }
@@ -0,0 +1,441 @@
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::gf128::Gf128;
+use crate::cyphers::modes::ctr::{CtrKeystream, Endianness};
+use crate::Error;
+
+pub mod nonce_reuse;
+
+const BLOCK_SIZE: usize = 16;
+
+/// The tag lengths in bytes permitted by NIST SP 800-38D
+const TAG_LENGTHS: [usize; 7] = [16, 15, 14, 13, 12, 8, 4];
+
+#[derive(Debug, PartialEq)]
+enum GcmError {
+    InvalidBlockSize(usize),
+    InvalidTagLength(usize),
+    EmptyIv,
+    AuthenticationFailed,
+}
+
+impl Display for GcmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GcmError::InvalidBlockSize(size) => {
+                write!(f, "gcm requires a 16 byte block cipher, found {size} bytes")
+            }
+            GcmError::InvalidTagLength(len) => write!(f, "invalid tag length {len}"),
+            GcmError::EmptyIv => write!(f, "iv must not be empty"),
+            GcmError::AuthenticationFailed => write!(f, "authentication tag does not match"),
+        }
+    }
+}
+
+impl StdError for GcmError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedMessage {
+    pub cipher_text: Vec<u8>,
+    pub iv: Vec<u8>,
+    pub tag: Vec<u8>,
+}
+
+/// Galois/counter mode: counter mode encryption authenticated with GHASH
+///
+/// Only defined for ciphers with a 16 byte block
+pub struct Gcm<C: BlockCipher> {
+    cipher: C,
+    hash_key: Gf128,
+    tag_length: usize,
+}
+
+impl<C: BlockCipher> Gcm<C> {
+    /// Returns a Gcm producing full length (16 byte) tags
+    ///
+    /// Errors
+    /// If the cipher does not have a 16 byte block
+    pub fn new(cipher: C) -> Result<Gcm<C>, Error> {
+        Self::with_tag_length(cipher, BLOCK_SIZE)
+    }
+
+    /// Returns a Gcm producing tags truncated to tag_length bytes
+    ///
+    /// Errors
+    /// If the cipher does not have a 16 byte block
+    /// If the tag length is not one of 16, 15, 14, 13, 12, 8 or 4 bytes
+    pub fn with_tag_length(cipher: C, tag_length: usize) -> Result<Gcm<C>, Error> {
+        if cipher.block_size() != BLOCK_SIZE {
+            return Err(Box::new(GcmError::InvalidBlockSize(cipher.block_size())));
+        }
+        if !TAG_LENGTHS.contains(&tag_length) {
+            return Err(Box::new(GcmError::InvalidTagLength(tag_length)));
+        }
+
+        let mut hash_key = [0; BLOCK_SIZE];
+        cipher.encrypt_block(&mut hash_key);
+
+        Ok(Gcm {
+            cipher,
+            hash_key: Gf128::from_block(&hash_key),
+            tag_length,
+        })
+    }
+
+    /// The authentication key H; the encryption of the all zero block
+    pub fn hash_key(&self) -> Gf128 {
+        self.hash_key
+    }
+
+    /// Encrypts the plain text and authenticates it along with the additional data
+    ///
+    /// Errors
+    /// If the iv is empty
+    pub fn encrypt(
+        &self,
+        plain_text: &[u8],
+        iv: &[u8],
+        additional_data: &[u8],
+    ) -> Result<EncryptedMessage, Error> {
+        let pre_counter_block = self.pre_counter_block(iv)?;
+        let cipher_text = self.gctr(&increment(pre_counter_block), plain_text);
+        let tag = self.tag(pre_counter_block, additional_data, &cipher_text);
+
+        Ok(EncryptedMessage {
+            cipher_text,
+            iv: iv.to_vec(),
+            tag,
+        })
+    }
+
+    /// Verifies the tag and, only if it matches, decrypts the cipher text
+    ///
+    /// Errors
+    /// If the iv is empty
+    /// If the tag is not the expected length or does not match the cipher text and additional data
+    pub fn decrypt(
+        &self,
+        message: &EncryptedMessage,
+        additional_data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        if message.tag.len() != self.tag_length {
+            return Err(Box::new(GcmError::InvalidTagLength(message.tag.len())));
+        }
+
+        let pre_counter_block = self.pre_counter_block(&message.iv)?;
+        let expected_tag = self.tag(pre_counter_block, additional_data, &message.cipher_text);
+
+        // compare every byte so the time taken doesn't reveal where the first mismatch is
+        let difference = expected_tag
+            .iter()
+            .zip(message.tag.iter())
+            .fold(0, |accum, (x, y)| accum | (x ^ y));
+        if difference != 0 {
+            return Err(Box::new(GcmError::AuthenticationFailed));
+        }
+
+        Ok(self.gctr(&increment(pre_counter_block), &message.cipher_text))
+    }
+
+    /// J0 in NIST SP 800-38D: a 96 bit iv is used directly, any other length is hashed
+    fn pre_counter_block(&self, iv: &[u8]) -> Result<[u8; BLOCK_SIZE], Error> {
+        if iv.is_empty() {
+            return Err(Box::new(GcmError::EmptyIv));
+        }
+
+        if iv.len() == 12 {
+            let mut block = [0; BLOCK_SIZE];
+            block[..12].copy_from_slice(iv);
+            block[15] = 1;
+            Ok(block)
+        } else {
+            Ok(ghash(self.hash_key, &[], iv).to_block())
+        }
+    }
+
+    fn tag(
+        &self,
+        pre_counter_block: [u8; BLOCK_SIZE],
+        additional_data: &[u8],
+        cipher_text: &[u8],
+    ) -> Vec<u8> {
+        let hash = ghash(self.hash_key, additional_data, cipher_text);
+        let mut tag = self.gctr(&pre_counter_block, &hash.to_block());
+        tag.truncate(self.tag_length);
+        tag
+    }
+
+    /// Counter mode keyed from the supplied initial counter block, incrementing only the last
+    /// 32 bits (big endian) of the counter modulo 2^32 as inc32 does
+    fn gctr(&self, initial_counter_block: &[u8; BLOCK_SIZE], data: &[u8]) -> Vec<u8> {
+        let initial_counter = u32::from_be_bytes(initial_counter_block[12..].try_into().unwrap());
+        let mut key_stream =
+            CtrKeystream::new(&self.cipher, &initial_counter_block[..12], Endianness::Big)
+                .expect("a 96 bit nonce leaves a 32 bit counter")
+                .with_initial_counter(initial_counter as u128)
+                .expect("the initial counter fits in 32 bits")
+                .wrapping();
+
+        let mut output = data.to_vec();
+        key_stream
+            .apply_keystream(&mut output)
+            .expect("the counter wraps instead of overflowing");
+        output
+    }
+}
+
+/// inc32 in NIST SP 800-38D; increments the last 32 bits of the block modulo 2^32
+fn increment(mut block: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    let counter = u32::from_be_bytes(block[12..].try_into().unwrap()).wrapping_add(1);
+    block[12..].copy_from_slice(&counter.to_be_bytes());
+    block
+}
+
+/// GHASH over the additional data and cipher text
+///
+/// Each input is zero padded to a whole number of blocks and followed by a block holding the
+/// 64 bit lengths in bits of both. The result is the polynomial
+/// X1 * H^n + X2 * H^(n-1) + ... + Xn * H evaluated with Horner's method
+pub fn ghash(hash_key: Gf128, additional_data: &[u8], cipher_text: &[u8]) -> Gf128 {
+    ghash_blocks(additional_data, cipher_text)
+        .into_iter()
+        .fold(Gf128::zero(), |accum, block| (accum + block) * hash_key)
+}
+
+/// The blocks X1..Xn hashed by GHASH: the padded additional data, the padded cipher text and
+/// the length block
+pub(crate) fn ghash_blocks(additional_data: &[u8], cipher_text: &[u8]) -> Vec<Gf128> {
+    let mut length_block = [0; BLOCK_SIZE];
+    length_block[..8].copy_from_slice(&(additional_data.len() as u64 * 8).to_be_bytes());
+    length_block[8..].copy_from_slice(&(cipher_text.len() as u64 * 8).to_be_bytes());
+
+    additional_data
+        .chunks(BLOCK_SIZE)
+        .chain(cipher_text.chunks(BLOCK_SIZE))
+        .chain([&length_block[..]])
+        .map(Gf128::from_block)
+        .collect()
+}
+
+/// Encrypts the plain text using AES-GCM with a full length tag
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or if the iv is empty
+pub fn encrypt(
+    plain_text: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    additional_data: &[u8],
+) -> EncryptedMessage {
+    Gcm::new(Aes::new(key).expect("invalid aes key"))
+        .expect("aes has a 16 byte block")
+        .encrypt(plain_text, iv, additional_data)
+        .expect("encryption failed")
+}
+
+/// Verifies and decrypts an AES-GCM message with a full length tag
+///
+/// Errors
+/// If the key is invalid or the message fails authentication
+pub fn decrypt(
+    message: &EncryptedMessage,
+    key: &[u8],
+    additional_data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    Gcm::new(Aes::new(key)?)?.decrypt(message, additional_data)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::aes::gcm::{decrypt, encrypt, ghash, EncryptedMessage, Gcm};
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::gf128::Gf128;
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    const KEY: &str = "feffe9928665731c6d6a8f9467308308";
+    const PLAIN_TEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+    const ADDITIONAL_DATA: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        Hex::from_str(hex).unwrap().bytes().to_vec()
+    }
+
+    /// Checks a test case from "The Galois/Counter Mode of Operation (GCM)" by McGrew and Viega
+    fn check_test_case(
+        key: &str,
+        iv: &str,
+        plain_text: &str,
+        additional_data: &str,
+        cipher_text: &str,
+        tag: &str,
+    ) {
+        let (key, iv, plain_text, additional_data) = (
+            bytes(key),
+            bytes(iv),
+            bytes(plain_text),
+            bytes(additional_data),
+        );
+
+        let encrypted = encrypt(&plain_text, &key, &iv, &additional_data);
+        assert_eq!(cipher_text, Hex::new(&encrypted.cipher_text).to_string());
+        assert_eq!(tag, Hex::new(&encrypted.tag).to_string());
+
+        let decrypted = decrypt(&encrypted, &key, &additional_data).unwrap();
+        assert_eq!(plain_text, decrypted);
+    }
+
+    #[test]
+    fn test_case_1() {
+        check_test_case(
+            "00000000000000000000000000000000",
+            "000000000000000000000000",
+            "",
+            "",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        );
+    }
+
+    #[test]
+    fn test_case_2() {
+        check_test_case(
+            "00000000000000000000000000000000",
+            "000000000000000000000000",
+            "00000000000000000000000000000000",
+            "",
+            "0388dace60b6a392f328c2b971b2fe78",
+            "ab6e47d42cec13bdf53a67b21257bddf",
+        );
+    }
+
+    #[test]
+    fn test_case_3() {
+        check_test_case(
+            KEY,
+            "cafebabefacedbaddecaf888",
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255",
+            "",
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+            "4d5c2af327cd64a62cf35abd2ba6fab4",
+        );
+    }
+
+    #[test]
+    fn test_case_4_additional_data() {
+        check_test_case(
+            KEY,
+            "cafebabefacedbaddecaf888",
+            PLAIN_TEXT,
+            ADDITIONAL_DATA,
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+            "5bc94fbc3221a5db94fae95ae7121a47",
+        );
+    }
+
+    #[test]
+    fn test_case_5_short_iv() {
+        check_test_case(
+            KEY,
+            "cafebabefacedbad",
+            PLAIN_TEXT,
+            ADDITIONAL_DATA,
+            "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c742373806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+            "3612d2e79e3b0785561be14aaca2fccb",
+        );
+    }
+
+    #[test]
+    fn test_case_6_long_iv() {
+        check_test_case(
+            KEY,
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            PLAIN_TEXT,
+            ADDITIONAL_DATA,
+            "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+            "619cc5aefffe0bfa462af43c1699d050",
+        );
+    }
+
+    #[test]
+    fn test_case_16_aes_256() {
+        check_test_case(
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308",
+            "cafebabefacedbaddecaf888",
+            PLAIN_TEXT,
+            ADDITIONAL_DATA,
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662",
+            "76fc6ece0f4e1768cddf8853bb2d551b",
+        );
+    }
+
+    #[test]
+    fn ghash_matches_test_case_2() {
+        let hash_key = Gf128::from_block(&bytes("66e94bd4ef8a2c3b884cfa59ca342b2e"));
+        let cipher_text = bytes("0388dace60b6a392f328c2b971b2fe78");
+
+        let hash = ghash(hash_key, &[], &cipher_text);
+
+        assert_eq!("f38cbb1ad69223dcc3457ae5b6b0f885", hash.to_string());
+    }
+
+    #[test]
+    fn truncated_tag_is_prefix_of_full_tag() {
+        let key = bytes(KEY);
+        let iv = bytes("cafebabefacedbaddecaf888");
+        let gcm = Gcm::with_tag_length(Aes::new(&key).unwrap(), 12).unwrap();
+
+        let encrypted = gcm.encrypt(&bytes(PLAIN_TEXT), &iv, &[]).unwrap();
+        let full = encrypt(&bytes(PLAIN_TEXT), &key, &iv, &[]);
+
+        assert_eq!(full.tag[..12], encrypted.tag);
+        assert!(gcm.decrypt(&encrypted, &[]).is_ok());
+        assert!(Gcm::with_tag_length(Aes::new(&key).unwrap(), 11).is_err());
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let key = bytes(KEY);
+        let additional_data = bytes(ADDITIONAL_DATA);
+        let encrypted = encrypt(
+            &bytes(PLAIN_TEXT),
+            &key,
+            &bytes("cafebabefacedbaddecaf888"),
+            &additional_data,
+        );
+
+        let mut flipped = encrypted.clone();
+        flipped.cipher_text[3] ^= 1;
+        assert!(decrypt(&flipped, &key, &additional_data).is_err());
+
+        let mut flipped = encrypted.clone();
+        flipped.tag[15] ^= 1;
+        assert!(decrypt(&flipped, &key, &additional_data).is_err());
+
+        assert!(decrypt(&encrypted, &key, &[]).is_err());
+    }
+
+    #[test]
+    fn eight_byte_block_cipher_is_rejected() {
+        assert!(Gcm::new(Feistel::new(&[0; 16])).is_err());
+    }
+
+    #[test]
+    fn empty_iv_is_rejected() {
+        let gcm = Gcm::new(Aes::new(&bytes(KEY)).unwrap()).unwrap();
+        assert!(gcm.encrypt(&[], &[], &[]).is_err());
+
+        let message = EncryptedMessage {
+            cipher_text: Vec::new(),
+            iv: Vec::new(),
+            tag: vec![0; 16],
+        };
+        assert!(gcm.decrypt(&message, &[]).is_err());
+    }
+}
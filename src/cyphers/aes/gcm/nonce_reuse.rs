@@ -0,0 +1,183 @@
+//! Recovers the GCM authentication key from two messages encrypted under the same key and iv
+//!
+//! Both tags are T = GHASH(H, A, C) + S where the mask S = E(K, J0) depends only on the key and
+//! iv, so adding two tags cancels S. Treating H as the unknown leaves a polynomial over
+//! GF(2^128) whose roots include H; knowing H and any one tag then recovers S, which is enough
+//! to forge a valid tag for any cipher text under that iv.
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::aes::gcm::{ghash, ghash_blocks, EncryptedMessage};
+use crate::cyphers::gf128::polynomial::Polynomial;
+use crate::cyphers::gf128::Gf128;
+use crate::Error;
+
+const TAG_LENGTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+enum NonceReuseError {
+    DifferentIvs,
+    TruncatedTag(usize),
+    IdenticalMessages,
+}
+
+impl Display for NonceReuseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NonceReuseError::DifferentIvs => {
+                write!(f, "messages were not encrypted under the same iv")
+            }
+            NonceReuseError::TruncatedTag(len) => {
+                write!(f, "a full 16 byte tag is required, found {len} bytes")
+            }
+            NonceReuseError::IdenticalMessages => {
+                write!(f, "messages are identical so reveal nothing about the key")
+            }
+        }
+    }
+}
+
+impl StdError for NonceReuseError {}
+
+/// Returns every value of the authentication key H consistent with both messages
+///
+/// The true key is always among the candidates; more messages under the same iv can be used to
+/// rule out the others with [narrow_hash_keys]
+///
+/// Errors
+/// If the messages have different ivs or truncated tags
+/// If the messages (including additional data) are identical
+pub fn candidate_hash_keys(
+    first: &EncryptedMessage,
+    first_additional_data: &[u8],
+    second: &EncryptedMessage,
+    second_additional_data: &[u8],
+) -> Result<Vec<Gf128>, Error> {
+    check_message(first)?;
+    check_message(second)?;
+    if first.iv != second.iv {
+        return Err(Box::new(NonceReuseError::DifferentIvs));
+    }
+
+    let difference = &tag_polynomial(first, first_additional_data)
+        + &tag_polynomial(second, second_additional_data);
+    if difference.degree().unwrap_or(0) == 0 {
+        return Err(Box::new(NonceReuseError::IdenticalMessages));
+    }
+
+    Ok(difference.roots())
+}
+
+/// Keeps only the candidate keys that give the same tag mask for every message
+///
+/// All messages must have been encrypted under the same key and iv
+pub fn narrow_hash_keys(
+    candidates: &[Gf128],
+    messages: &[(&EncryptedMessage, &[u8])],
+) -> Vec<Gf128> {
+    candidates
+        .iter()
+        .filter(|hash_key| {
+            let mut masks = messages
+                .iter()
+                .map(|(message, additional_data)| tag_mask(**hash_key, message, additional_data));
+            match masks.next() {
+                Some(first) => masks.all(|mask| mask == first),
+                None => true,
+            }
+        })
+        .copied()
+        .collect()
+}
+
+/// Forges a message with the chosen cipher text and additional data using a recovered hash key
+///
+/// The forgery uses the iv of the known message, which must have been encrypted under that key
+pub fn forge(
+    hash_key: Gf128,
+    known: &EncryptedMessage,
+    known_additional_data: &[u8],
+    cipher_text: &[u8],
+    additional_data: &[u8],
+) -> EncryptedMessage {
+    let mask = tag_mask(hash_key, known, known_additional_data);
+    let tag = ghash(hash_key, additional_data, cipher_text) + mask;
+
+    EncryptedMessage {
+        cipher_text: cipher_text.to_vec(),
+        iv: known.iv.clone(),
+        tag: tag.to_block().to_vec(),
+    }
+}
+
+fn check_message(message: &EncryptedMessage) -> Result<(), Error> {
+    if message.tag.len() != TAG_LENGTH {
+        return Err(Box::new(NonceReuseError::TruncatedTag(message.tag.len())));
+    }
+    Ok(())
+}
+
+/// S = T + GHASH(H, A, C)
+fn tag_mask(hash_key: Gf128, message: &EncryptedMessage, additional_data: &[u8]) -> Gf128 {
+    Gf128::from_block(&message.tag) + ghash(hash_key, additional_data, &message.cipher_text)
+}
+
+/// The polynomial X1 * H^n + ... + Xn * H + T, which evaluates to S at the true hash key
+fn tag_polynomial(message: &EncryptedMessage, additional_data: &[u8]) -> Polynomial {
+    let mut coefficients = vec![Gf128::from_block(&message.tag)];
+    coefficients.extend(
+        ghash_blocks(additional_data, &message.cipher_text)
+            .into_iter()
+            .rev(),
+    );
+    Polynomial::new(coefficients)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::aes::gcm::nonce_reuse::{candidate_hash_keys, forge, narrow_hash_keys};
+    use crate::cyphers::aes::gcm::Gcm;
+    use crate::cyphers::aes::get_random_bytes;
+
+    #[test]
+    fn forges_tag_from_reused_nonce() {
+        let gcm = Gcm::new(Aes::new(&get_random_bytes(16)).unwrap()).unwrap();
+        let iv = get_random_bytes(12);
+
+        let first = gcm
+            .encrypt(b"transfer 100 to alice", &iv, b"header")
+            .unwrap();
+        let second = gcm
+            .encrypt(b"transfer 250 to bob, thanks", &iv, b"other header")
+            .unwrap();
+        let third = gcm.encrypt(b"and a third message", &iv, b"").unwrap();
+
+        let candidates = candidate_hash_keys(&first, b"header", &second, b"other header").unwrap();
+        assert!(candidates.contains(&gcm.hash_key()));
+
+        let narrowed = narrow_hash_keys(
+            &candidates,
+            &[
+                (&first, &b"header"[..]),
+                (&second, &b"other header"[..]),
+                (&third, &b""[..]),
+            ],
+        );
+        assert_eq!(vec![gcm.hash_key()], narrowed);
+
+        let cipher_text = b"an attacker chosen cipher text".to_vec();
+        let forged = forge(narrowed[0], &first, b"header", &cipher_text, b"forged");
+        assert!(gcm.decrypt(&forged, b"forged").is_ok());
+    }
+
+    #[test]
+    fn different_ivs_are_rejected() {
+        let gcm = Gcm::new(Aes::new(&get_random_bytes(16)).unwrap()).unwrap();
+        let first = gcm.encrypt(b"one", &get_random_bytes(12), b"").unwrap();
+        let second = gcm.encrypt(b"two", &get_random_bytes(12), b"").unwrap();
+
+        assert!(candidate_hash_keys(&first, b"", &second, b"").is_err());
+        assert!(candidate_hash_keys(&first, b"", &first, b"").is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::modes::ofb::Ofb;
+
+/// Encrypts the plain text with AES in OFB mode; no padding is added
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn encrypt(plain_text: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Ofb::new(Aes::new(key).expect("invalid aes key")).apply_keystream(plain_text, iv)
+}
+
+/// Decrypts an AES OFB cipher text
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Ofb::new(Aes::new(key).expect("invalid aes key")).apply_keystream(encrypted_message, iv)
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::ofb::{decrypt, encrypt};
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        Hex::from_str(hex).unwrap().bytes().to_vec()
+    }
+
+    /// F.4.5 and F.4.6 of NIST SP 800-38A
+    #[test]
+    fn ofb_matches_nist_vectors() {
+        let key = bytes("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4");
+        let iv = bytes("000102030405060708090a0b0c0d0e0f");
+        let plain_text = bytes("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let cipher_text = bytes("dc7e84bfda79164b7ecd8486985d38604febdc6740d20b3ac88f6ad82a4fb08d71ab47a086e86eedf39d1c5bba97c4080126141d67f37be8538f5a8be740e484");
+
+        assert_eq!(cipher_text, encrypt(&plain_text, &key, &iv));
+        assert_eq!(plain_text, decrypt(&cipher_text, &key, &iv));
+    }
+}
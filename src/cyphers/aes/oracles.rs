@@ -1,3 +1,12 @@
-pub mod ecb_oracle;
+pub mod bit_flipping;
 pub mod cbc_oracle;
-pub mod padding_oracle;
\ No newline at end of file
+pub mod ecb_cut_paste;
+pub mod ecb_oracle;
+pub mod edit_oracle;
+pub mod fingerprint;
+pub mod instrumented;
+pub mod key_is_iv;
+pub mod mode_classifier;
+pub mod padding_oracle;
+
+mod http;
@@ -0,0 +1,253 @@
+//! Rewrites known plain text inside a cipher text without the key
+//!
+//! Flipping a bit of CBC cipher text flips the same bit of the plain text one block later, at
+//! the cost of garbling the block that was changed. In CTR mode the plain text bit at the same
+//! position flips and nothing else changes. Either way xor-ing the cipher text with
+//! known ^ desired turns the known plain text into the desired plain text.
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::Error;
+
+#[derive(Debug, PartialEq)]
+enum BitFlipError {
+    LengthMismatch { known: usize, desired: usize },
+    OutOfRange { end: usize, length: usize },
+    FirstBlock,
+    SpansBlocks,
+    NoPrefixAccepted,
+}
+
+impl Display for BitFlipError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BitFlipError::LengthMismatch { known, desired } => write!(
+                f,
+                "known plain text is {known} bytes but desired plain text is {desired} bytes"
+            ),
+            BitFlipError::OutOfRange { end, length } => write!(
+                f,
+                "target ends at byte {end} of a {length} byte cipher text"
+            ),
+            BitFlipError::FirstBlock => write!(
+                f,
+                "target is in the first block, which can only be changed through the iv"
+            ),
+            BitFlipError::SpansBlocks => write!(
+                f,
+                "target crosses a block boundary, so flipping it would garble part of itself"
+            ),
+            BitFlipError::NoPrefixAccepted => {
+                write!(f, "no prefix length gave an accepted cipher text")
+            }
+        }
+    }
+}
+
+impl StdError for BitFlipError {}
+
+/// Where the known plain text is and what it should become
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The number of bytes the oracle puts before the attacker's input
+    pub prefix_length: usize,
+    /// Where in the input the bytes to change start
+    pub offset: usize,
+    /// The input at the offset as it was encrypted
+    pub known: Vec<u8>,
+    /// What the input at the offset should decrypt to instead
+    pub desired: Vec<u8>,
+}
+
+impl Layout {
+    /// The same layout with a different prefix length, for [search_prefix_length]
+    pub fn with_prefix_length(&self, prefix_length: usize) -> Layout {
+        Layout {
+            prefix_length,
+            ..self.clone()
+        }
+    }
+
+    /// The position of the target in the plain text
+    fn start(&self) -> usize {
+        self.prefix_length + self.offset
+    }
+
+    fn mask(&self) -> Result<Vec<u8>, Error> {
+        if self.known.len() != self.desired.len() {
+            return Err(Box::new(BitFlipError::LengthMismatch {
+                known: self.known.len(),
+                desired: self.desired.len(),
+            }));
+        }
+        Ok(self
+            .known
+            .iter()
+            .zip(&self.desired)
+            .map(|(x, y)| x ^ y)
+            .collect())
+    }
+}
+
+/// Flips the block before the target so that it decrypts to the desired plain text
+///
+/// The block that is flipped decrypts to garbage, so the input should start with a block of
+/// filler for the target to follow
+///
+/// Errors
+/// If the known and desired plain texts differ in length
+/// If the target is in the first block, crosses a block boundary or runs past the cipher text
+pub fn flip_cbc(cipher_text: &[u8], block_size: usize, layout: &Layout) -> Result<Vec<u8>, Error> {
+    let mask = layout.mask()?;
+    let start = layout.start();
+    check_range(cipher_text, start + mask.len())?;
+    if start < block_size {
+        return Err(Box::new(BitFlipError::FirstBlock));
+    }
+    if !mask.is_empty() && start / block_size != (start + mask.len() - 1) / block_size {
+        return Err(Box::new(BitFlipError::SpansBlocks));
+    }
+
+    Ok(flip(cipher_text, start - block_size, &mask))
+}
+
+/// Flips the target in place so that it decrypts to the desired plain text
+///
+/// Errors
+/// If the known and desired plain texts differ in length, or the target runs past the cipher text
+pub fn flip_ctr(cipher_text: &[u8], layout: &Layout) -> Result<Vec<u8>, Error> {
+    let mask = layout.mask()?;
+    let start = layout.start();
+    check_range(cipher_text, start + mask.len())?;
+
+    Ok(flip(cipher_text, start, &mask))
+}
+
+/// Tries each prefix length until the oracle accepts the flipped cipher text, for when the
+/// length of the oracle's prefix is unknown
+///
+/// `flip` builds the cipher text for a prefix length, e.g. with [flip_cbc] and
+/// [Layout::with_prefix_length]; lengths it returns an error for are skipped. Returns the prefix
+/// length and cipher text that were accepted
+///
+/// Errors
+/// If no prefix length is accepted
+pub fn search_prefix_length<L, F, A>(
+    prefix_lengths: L,
+    flip: F,
+    accepted: A,
+) -> Result<(usize, Vec<u8>), Error>
+where
+    L: IntoIterator<Item = usize>,
+    F: Fn(usize) -> Result<Vec<u8>, Error>,
+    A: Fn(&[u8]) -> bool,
+{
+    prefix_lengths
+        .into_iter()
+        .find_map(|prefix_length| {
+            let cipher_text = flip(prefix_length).ok()?;
+            accepted(&cipher_text).then_some((prefix_length, cipher_text))
+        })
+        .ok_or_else(|| Box::new(BitFlipError::NoPrefixAccepted) as Error)
+}
+
+fn check_range(cipher_text: &[u8], end: usize) -> Result<(), Error> {
+    if end > cipher_text.len() {
+        return Err(Box::new(BitFlipError::OutOfRange {
+            end,
+            length: cipher_text.len(),
+        }));
+    }
+    Ok(())
+}
+
+fn flip(cipher_text: &[u8], start: usize, mask: &[u8]) -> Vec<u8> {
+    let mut flipped = cipher_text.to_vec();
+    flipped[start..start + mask.len()]
+        .iter_mut()
+        .zip(mask)
+        .for_each(|(byte, bits)| *byte ^= bits);
+    flipped
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::ctr::CTRSampleEncryptions;
+    use crate::cyphers::aes::oracles::bit_flipping::{
+        flip_cbc, flip_ctr, search_prefix_length, Layout,
+    };
+    use crate::cyphers::aes::oracles::cbc_oracle::CBCOracle;
+
+    fn admin_layout(prefix_length: usize) -> Layout {
+        Layout {
+            prefix_length,
+            offset: 16,
+            known: b":admin<true".to_vec(),
+            desired: b";admin=true".to_vec(),
+        }
+    }
+
+    #[test]
+    fn flips_cbc_cipher_text() {
+        let oracle = CBCOracle::new();
+        let encrypted = oracle.encrypt_with_message(&b"sixteen filler..:admin<true".to_vec());
+        assert!(!oracle.is_admin(&encrypted.cipher_text));
+
+        let flipped = flip_cbc(&encrypted.cipher_text, 16, &admin_layout(32)).unwrap();
+        assert!(oracle.is_admin(&flipped));
+    }
+
+    #[test]
+    fn flips_ctr_cipher_text() {
+        let oracle = CTRSampleEncryptions::new();
+        let mut encrypted = oracle.bit_flip_demo(b"sixteen filler..:admin<true");
+
+        encrypted.cipher_text = flip_ctr(&encrypted.cipher_text, &admin_layout(32)).unwrap();
+        assert!(oracle.bit_flip_success(encrypted));
+    }
+
+    #[test]
+    fn finds_unknown_prefix_length() {
+        let oracle = CBCOracle::new();
+        let encrypted = oracle.encrypt_with_message(&b"sixteen filler..:admin<true".to_vec());
+        let layout = admin_layout(0);
+
+        let (prefix_length, _) = search_prefix_length(
+            0..64,
+            |prefix_length| {
+                flip_cbc(
+                    &encrypted.cipher_text,
+                    16,
+                    &layout.with_prefix_length(prefix_length),
+                )
+            },
+            |cipher_text| oracle.is_admin(cipher_text),
+        )
+        .unwrap();
+        assert_eq!(32, prefix_length);
+    }
+
+    #[test]
+    fn invalid_layouts_are_rejected() {
+        let cipher_text = [0; 48];
+        let layout = Layout {
+            prefix_length: 0,
+            offset: 12,
+            known: vec![0; 8],
+            desired: vec![1; 8],
+        };
+
+        assert!(flip_cbc(&cipher_text, 16, &layout).is_err());
+        assert!(flip_cbc(&cipher_text, 16, &layout.with_prefix_length(16)).is_err());
+        assert!(flip_cbc(&cipher_text, 16, &layout.with_prefix_length(20)).is_ok());
+        assert!(flip_ctr(&cipher_text, &layout.with_prefix_length(40)).is_err());
+        assert!(flip_ctr(
+            &cipher_text,
+            &Layout {
+                desired: vec![1; 7],
+                ..layout
+            }
+        )
+        .is_err());
+    }
+}
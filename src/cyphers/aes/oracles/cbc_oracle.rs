@@ -1,6 +1,9 @@
 use crate::cyphers::aes;
 use crate::cyphers::aes::cbc;
+use crate::cyphers::aes::oracles::padding_oracle::PaddingOracle;
+use crate::cyphers::padding::pkcs7;
 use crate::encoding::Digest;
+use crate::Error;
 
 pub struct CBCOracle {
     pub key: [u8; 16],
@@ -87,7 +90,10 @@ impl CBCOracle {
     }
 
     pub fn is_admin(&self, cipher_text: &[u8]) -> bool {
-        let plain_text = cbc::decrypt(cipher_text, &self.key, &self.iv);
+        let plain_text = match cbc::try_decrypt(cipher_text, &self.key, &self.iv) {
+            Ok(plain_text) => plain_text,
+            Err(_) => return false,
+        };
         // checking for valid utf-8 will cause the attack in challenge 16 to fail most of the time
         let message = String::from_utf8_lossy(&plain_text);
         message.contains(";admin=true;")
@@ -99,10 +105,24 @@ impl CBCOracle {
     }
 }
 
+/// Behaves like a service that reports a distinct error when a message's padding is wrong
+impl PaddingOracle for CBCOracle {
+    fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error> {
+        if iv.len() != self.key.len() {
+            return Ok(false);
+        }
+        Ok(match cbc::try_decrypt(cipher_text, &self.key, iv) {
+            Ok(plain_text) => pkcs7::try_unpad(&plain_text, self.key.len()).is_ok(),
+            Err(_) => false,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cyphers::aes::cbc;
     use crate::cyphers::aes::oracles::cbc_oracle::CBCOracle;
+    use crate::cyphers::aes::oracles::padding_oracle::PaddingOracleAttack;
 
     #[test]
     fn encrypt_correctly_sanitises_text() {
@@ -130,4 +150,16 @@ mod tests {
         let is_admin = oracle.is_admin(&encrypted.cipher_text);
         assert!(!is_admin);
     }
+
+    #[test]
+    fn padding_oracle_forges_admin() {
+        let oracle = CBCOracle::new();
+        let forged = PaddingOracleAttack::new(16)
+            .encrypt(&oracle, b";admin=true;")
+            .unwrap();
+
+        // is_admin always decrypts with the oracle's own iv, so the forged iv is sent as the
+        // first cipher text block and only garbles the block in front of the forged text
+        assert!(oracle.is_admin(&[forged.iv, forged.cipher_text].concat()));
+    }
 }
@@ -0,0 +1,220 @@
+//! Forges ECB encrypted `key=value&...` cookies by cutting and pasting whole blocks
+//!
+//! The attacker controls one value in the cookie, e.g. the email passed to
+//! [Profile::profile_for](crate::profile::Profile::profile_for). Choosing its length lines up
+//! the end of `role=` with a block boundary, so every block before that boundary can be kept;
+//! a second input places the chosen value, padded exactly as the oracle would pad it, in a
+//! block of its own. Joining the two gives a cookie ending `role=admin` that the oracle never
+//! encrypted.
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::padding::pkcs7;
+use crate::encoding::structured_cookie::StructuredCookie;
+use crate::Error;
+
+/// The longest input tried while looking for the block size
+const MAX_BLOCK_SIZE: usize = 64;
+const FILLER: char = 'A';
+const CHANGED: char = 'B';
+
+#[derive(Debug, PartialEq)]
+enum CutPasteError {
+    BlockSizeNotFound,
+    InputNotFound,
+    ForgeryRejected,
+}
+
+impl Display for CutPasteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CutPasteError::BlockSizeNotFound => {
+                write!(f, "cipher text length never changed, not a block cipher")
+            }
+            CutPasteError::InputNotFound => write!(
+                f,
+                "repeating the input never repeated a block, the oracle is not using ECB mode"
+            ),
+            CutPasteError::ForgeryRejected => {
+                write!(f, "no alignment produced a cookie with the chosen value")
+            }
+        }
+    }
+}
+
+impl StdError for CutPasteError {}
+
+/// Where the attacker's input lands in the plain text the oracle encrypts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub block_size: usize,
+    /// The number of bytes before the input
+    pub prefix_length: usize,
+    /// The number of bytes after the input
+    pub suffix_length: usize,
+}
+
+/// Finds the block size and where the input sits between the oracle's prefix and suffix
+///
+/// Errors
+/// If the oracle is not encrypting with a block cipher in ECB mode
+pub fn find_layout<E: Fn(&str) -> Vec<u8>>(encrypt: E) -> Result<Layout, Error> {
+    // the cipher text grows by a block once the input fills the last block's padding
+    let empty_length = encrypt("").len();
+    let (input_length, block_size) = (1..=MAX_BLOCK_SIZE)
+        .map(|length| (length, encrypt(&filler(length)).len() - empty_length))
+        .find(|(_, growth)| *growth > 0)
+        .ok_or(CutPasteError::BlockSizeNotFound)?;
+    let fixed_length = empty_length
+        .checked_sub(input_length)
+        .ok_or(CutPasteError::InputNotFound)?;
+
+    // two whole blocks of input encrypt to the same block twice once the prefix is padded out;
+    // the filler is fenced in by another byte, so a prefix or suffix that starts or ends with the
+    // filler can't make the blocks repeat early
+    let prefix_length = (0..block_size)
+        .find_map(|padding| {
+            let input = format!("{CHANGED}{}{CHANGED}", filler(padding + 2 * block_size));
+            let cipher_text = encrypt(&input);
+            let blocks: Vec<&[u8]> = cipher_text.chunks(block_size).collect();
+            let repeat = blocks.windows(2).position(|pair| pair[0] == pair[1])?;
+            (repeat * block_size).checked_sub(padding + 1)
+        })
+        .ok_or(CutPasteError::InputNotFound)?;
+
+    Ok(Layout {
+        block_size,
+        prefix_length,
+        suffix_length: fixed_length
+            .checked_sub(prefix_length)
+            .ok_or(CutPasteError::InputNotFound)?,
+    })
+}
+
+/// Forges a cipher text whose cookie gives the field the chosen value
+///
+/// The field must be the last in the cookie. Its current value is unknown, so each possible
+/// length is tried and the forgery checked with the decrypt function until one is accepted
+///
+/// Errors
+/// If the oracle is not encrypting with a block cipher in ECB mode
+/// If no forgery decrypts to a cookie with the chosen value, e.g. because the oracle strips some
+/// of its characters
+pub fn forge<E, D>(encrypt: E, decrypt: D, field: &str, value: &str) -> Result<Vec<u8>, Error>
+where
+    E: Fn(&str) -> Vec<u8>,
+    D: Fn(&[u8]) -> Result<StructuredCookie, Error>,
+{
+    let layout = find_layout(&encrypt)?;
+    let forged_blocks = value_blocks(&encrypt, &layout, value);
+
+    for value_length in 0..=layout.suffix_length {
+        let cipher_text = [
+            &aligned_blocks(&encrypt, &layout, value_length),
+            &forged_blocks[..],
+        ]
+        .concat();
+        let accepted = decrypt(&cipher_text)
+            .map(|cookie| cookie.get(field).as_deref() == Some(value))
+            .unwrap_or(false);
+        if accepted {
+            return Ok(cipher_text);
+        }
+    }
+
+    Err(Box::new(CutPasteError::ForgeryRejected))
+}
+
+/// The encrypted blocks holding the padded value, found by starting the input on a block
+/// boundary
+fn value_blocks<E: Fn(&str) -> Vec<u8>>(encrypt: E, layout: &Layout, value: &str) -> Vec<u8> {
+    let block_size = layout.block_size;
+    let alignment = (block_size - layout.prefix_length % block_size) % block_size;
+    let padded = pkcs7::pad(value.as_bytes(), block_size);
+    let input = filler(alignment) + &String::from_utf8(padded).expect("padding bytes are ascii");
+
+    let start = layout.prefix_length + alignment;
+    encrypt(&input)[start..start + padded_length(value.len(), block_size)].to_vec()
+}
+
+/// The encrypted blocks up to the start of the last value, assuming it is value_length bytes
+fn aligned_blocks<E: Fn(&str) -> Vec<u8>>(
+    encrypt: E,
+    layout: &Layout,
+    value_length: usize,
+) -> Vec<u8> {
+    let block_size = layout.block_size;
+    let kept = layout.prefix_length + layout.suffix_length - value_length;
+    let input_length = (block_size - kept % block_size) % block_size;
+
+    let mut cipher_text = encrypt(&filler(input_length));
+    cipher_text.truncate(kept + input_length);
+    cipher_text
+}
+
+fn padded_length(length: usize, block_size: usize) -> usize {
+    (length / block_size + 1) * block_size
+}
+
+fn filler(length: usize) -> String {
+    FILLER.to_string().repeat(length)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::oracles::ecb_cut_paste::{find_layout, forge, Layout};
+    use crate::encoding::structured_cookie::StructuredCookie;
+    use crate::profile::{Profile, ProfileEncrypter};
+
+    #[test]
+    fn finds_profile_layout() {
+        let encrypter = ProfileEncrypter::new();
+        let layout = find_layout(|email| encrypter.encrypt(&Profile::profile_for(email))).unwrap();
+
+        let expected = Layout {
+            block_size: 16,
+            prefix_length: "email=".len(),
+            suffix_length: "&uid=32&role=user".len(),
+        };
+        assert_eq!(expected, layout);
+    }
+
+    #[test]
+    fn finds_layout_when_prefix_and_suffix_look_like_filler() {
+        let encrypter = ProfileEncrypter::new();
+        let layout = find_layout(|email| {
+            encrypter.encrypt(&Profile::profile_for(&format!("fooAAA{email}AAAA")))
+        })
+        .unwrap();
+
+        let expected = Layout {
+            block_size: 16,
+            prefix_length: "email=fooAAA".len(),
+            suffix_length: "AAAA&uid=32&role=user".len(),
+        };
+        assert_eq!(expected, layout);
+    }
+
+    #[test]
+    fn forges_admin_profile() {
+        let encrypter = ProfileEncrypter::new();
+        let encrypt = |email: &str| {
+            encrypter.encrypt(&Profile::profile_for(&format!(
+                "sam.rosenberg@secret.com{email}"
+            )))
+        };
+        let decrypt = |cipher_text: &[u8]| {
+            encrypter
+                .try_decrypt(cipher_text)
+                .map(StructuredCookie::from)
+        };
+
+        let forged = forge(encrypt, decrypt, "role", "admin").unwrap();
+
+        let profile = encrypter.decrypt(&forged);
+        assert_eq!(
+            Some("admin".to_string()),
+            StructuredCookie::from(profile).get("role")
+        );
+    }
+}
@@ -1,173 +1,57 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
-use openssl::rand as ssl_rand;
-use rand::Rng;
+use rand::{Rng, RngCore};
 
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::aes::oracles::fingerprint;
+use crate::cyphers::aes::oracles::mode_classifier::ModeClassifier;
 use crate::cyphers::aes::AesMode;
 use crate::cyphers::aes::{self, cbc, ecb};
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::modes::ecb::Ecb;
 use crate::encoding::base64::Base64;
 use crate::encoding::Digest;
+use crate::Error;
 
-fn prefix_length(iteration: usize, key_length: usize) -> usize {
-    let modulus = iteration % key_length;
-    if modulus == 0 {
-        0
-    } else {
-        key_length - modulus
-    }
-}
+pub mod byte_at_a_time;
 
 pub trait ECBOracle {
     fn encrypt<T: Digest>(&self, stimulus: T) -> Vec<u8>;
 
-    /// Decrypts the message encoded by the oracle
-    ///
-    /// Assumptions:
-    /// The oracle uses a consistent key and fixed length plain text
-    ///
-    /// The plain text may have arbitrary nonsense appended
-    /// prior to the stimulus supplied by the encrypt method
-    /// If so, this nonsense is always the same sequence of bytes
-    /// (This method may still work if the value of the bytes changes,
-    /// but the length remains the same, but this is not guaranteed
+    /// Encrypts the stimulus, for oracles that can fail or refuse to answer
     ///
-    fn decrypt(&self) -> Vec<u8> {
-        let key_length = self.find_key_length().expect("key length > 128");
-        let (known_byte_index, known_bytes_needed) = self.locate_known_bytes(key_length);
-
-        let encrypted_message = self.encrypt("A".repeat(known_bytes_needed).as_bytes().to_vec());
-        let junk_byte_length = known_byte_index + key_length;
-        let target_decryption_length = encrypted_message.len() - junk_byte_length;
-        let positioning_bytes = known_bytes_needed - key_length;
-
-        let mut decrypted_message = Vec::new();
-        (1..target_decryption_length).for_each(|i| {
-            let prefix = "A"
-                .repeat(positioning_bytes + prefix_length(i, key_length))
-                .into_bytes();
-            let known_bytes = [&prefix, &decrypted_message[..]].concat();
-            let byte_map = self.last_byte_map(&known_bytes, known_byte_index, positioning_bytes);
-            let encrypted_message = self.encrypt(&prefix.to_vec());
-
-            // if the decrypted message + key_length is more than the encrypted message then we've reached the end
-            // there may be up to key_length - 1 extra padding bytes
-            // this method will fall over at this point because the pkcs#7 padding standard means the decrypted byte is different
-            // i.e. the first iteration will end '\x01', the second '\x02\x02' etc
-            if decrypted_message.len() + known_byte_index + key_length < encrypted_message.len() {
-                decrypted_message.push(
-                    *byte_map
-                        .get(
-                            &encrypted_message[known_byte_index
-                                ..=(known_byte_index + (known_bytes.len() - positioning_bytes))],
-                        )
-                        .expect("no matching byte"),
-                );
-            }
-        });
-        decrypted_message
+    /// Errors
+    /// If the oracle could not be queried; by default the oracle never fails
+    fn try_encrypt<T: Digest>(&self, stimulus: T) -> Result<Vec<u8>, Error> {
+        Ok(self.encrypt(stimulus))
     }
 
-    /// given a slice of known bytes, returns a map of slices with every possible byte appended
-    /// map is keyed on the possible slices, the value being the appended byte
-    fn last_byte_map(
-        &self,
-        known_bytes: &[u8],
-        index: usize,
-        positioning_bytes: usize,
-    ) -> HashMap<Vec<u8>, u8> {
-        (0..=255)
-            .map(|i| {
-                let mut last_byte_possibility = known_bytes.to_vec();
-                last_byte_possibility.push(i);
-                last_byte_possibility = self.encrypt(last_byte_possibility);
-                last_byte_possibility = last_byte_possibility
-                    [index..=(index + known_bytes.len() - positioning_bytes)]
-                    .to_vec();
-                (last_byte_possibility, i)
-            })
-            .collect()
-    }
-
-    /// Returns the index in the cipher text that represents the end of an unknown prefix,
-    /// and the number of bytes stimulus required to pad that prefix to a a full block
-    /// if used for an oracle that does not prepend a random byte sequence will return (0, 0)o
+    /// Decrypts the secret the oracle appends to the stimulus, one byte at a time
     ///
-    /// Currently this method will never return if either the random byte sequence or the target
-    /// cipher text contains duplicated blocks.
-    /// Fixing this would require tracking the frequency of each duplicate block and tracking
-    /// which one increases as the outer loop repeats
+    /// The oracle may put a prefix before the stimulus, whose length may change on every call;
+    /// see [byte_at_a_time] for how the attack locates the stimulus
     ///
-    fn locate_known_bytes(&self, key_length: usize) -> (usize, usize) {
-        for i in 3.. {
-            let known_bytes = "A".repeat(key_length * i).into_bytes();
-            let encrypted_known_bytes = self.encrypt(known_bytes);
-            let duplicate_blocks = encrypted_known_bytes.map_duplicate_blocks(key_length);
-
-            // if only 1 duplicate, must be this and therefore complete
-            if duplicate_blocks.len() == 1 {
-                let (known_block, occurrences) = duplicate_blocks.into_iter().next().unwrap();
-                // find last instance of encrypted known i
-                let last_known_byte_index = encrypted_known_bytes
-                    .chunks(key_length)
-                    .enumerate()
-                    .find(|(_, block)| block == &known_block)
-                    .map(|(i, _)| i)
-                    .unwrap()
-                    * key_length;
-
-                let mut known_bytes_needed = 0;
-                for j in (0..(i * key_length)).rev() {
-                    let known_bytes = "A".repeat(j).into_bytes();
-                    let encrypted_known_bytes = self.encrypt(known_bytes);
-                    let duplicate_blocks = encrypted_known_bytes.map_duplicate_blocks(key_length);
-
-                    if duplicate_blocks.into_values().next().unwrap_or(0) < occurrences {
-                        known_bytes_needed = j + 1;
-                        known_bytes_needed -= (occurrences - 1) * key_length;
-                        break;
-                    }
-                }
-
-                return (last_known_byte_index, known_bytes_needed);
-            }
-        }
-
-        // note; this is actually unreachable
-        (0, 0)
+    /// Errors
+    /// If the oracle fails, is not using ECB mode or changes the secret between calls
+    fn decrypt(&self) -> Result<Vec<u8>, Error> {
+        byte_at_a_time::decrypt(self)
     }
+
     /// finds the length of the key being used by the oracle
-    /// Returns none if the key is longer than 128 bytes or if a block cypher is not used
+    /// Returns none if a block cypher is not used, if the oracle's prefix or suffix change
+    /// length between calls, or if the oracle fails
     ///
-    /// Panics
-    /// If the encryption type is not ECB
+    /// See [fingerprint](fingerprint::fingerprint) for everything else that can be learnt about
+    /// the oracle
     fn find_key_length(&self) -> Option<usize> {
-        let mut key_length = None;
-        (1..128).for_each(|i| {
-            let test_message_a = "A".repeat(i).into_bytes();
-            let encrypted_message_a = self.encrypt(test_message_a);
-
-            let test_message_b = "A".repeat(i + 1).into_bytes();
-            let encrypted_message_b = self.encrypt(test_message_b);
-
-            if encrypted_message_b.len() > encrypted_message_a.len() {
-                key_length = Some(encrypted_message_b.len() - encrypted_message_a.len());
-            }
-        });
-
-        if let Some(length) = key_length {
-            let aes_mode = detect_aes_type(self.encrypt("A".repeat(length * 3).into_bytes()));
-            if aes_mode != AesMode::ECB {
-                panic!("ECBOracle not using ECB mode");
-            }
-        }
-
-        key_length
+        fingerprint::try_fingerprint(|input| self.try_encrypt(input))
+            .ok()?
+            .block_size
     }
 }
 
-pub struct RandomPrefixECBOracle {
-    key: [u8; 16],
+pub struct RandomPrefixECBOracle<C: BlockCipher = Aes> {
+    ecb: Ecb<C>,
     prefix: Vec<u8>,
     suffix: Base64,
 }
@@ -175,27 +59,33 @@ pub struct RandomPrefixECBOracle {
 impl Default for RandomPrefixECBOracle {
     fn default() -> Self {
         let key = aes::generate_16_bit_key();
+        Self::with_cipher(Aes::new(&key).expect("key is 16 bytes"))
+    }
+}
 
+impl<C: BlockCipher> RandomPrefixECBOracle<C> {
+    /// Returns a new oracle that encrypts using the supplied block cipher
+    pub fn with_cipher(cipher: C) -> Self {
         let prefix_length = rand::thread_rng().gen_range(1..=255);
         let mut prefix = vec![0; prefix_length];
-        ssl_rand::rand_bytes(&mut prefix).expect("ssl rand failed");
+        rand::thread_rng().fill_bytes(&mut prefix);
 
         let suffix = Base64::from_str(
             "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK",
         ).unwrap();
 
         RandomPrefixECBOracle {
-            key,
+            ecb: Ecb::new(cipher),
             prefix,
             suffix,
         }
     }
 }
 
-impl ECBOracle for RandomPrefixECBOracle {
+impl<C: BlockCipher> ECBOracle for RandomPrefixECBOracle<C> {
     fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
         let message = [&self.prefix, message.bytes(), self.suffix.bytes()].concat();
-        ecb::encrypt(&self.key, message)
+        self.ecb.encrypt(&message)
     }
 }
 
@@ -205,19 +95,71 @@ impl RandomPrefixECBOracle {
     }
 }
 
-pub struct BasicECBOracle {
-    key: [u8; 16],
+/// Like [RandomPrefixECBOracle], but a new prefix of random length is generated for every message
+pub struct VariablePrefixECBOracle<C: BlockCipher = Aes> {
+    ecb: Ecb<C>,
+    suffix: Base64,
+}
+
+impl Default for VariablePrefixECBOracle {
+    fn default() -> Self {
+        let key = aes::generate_16_bit_key();
+        Self::with_cipher(Aes::new(&key).expect("key is 16 bytes"))
+    }
+}
+
+impl<C: BlockCipher> VariablePrefixECBOracle<C> {
+    /// Returns a new oracle that encrypts using the supplied block cipher
+    pub fn with_cipher(cipher: C) -> Self {
+        let suffix = Base64::from_str(
+            "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK",
+        ).unwrap();
+
+        VariablePrefixECBOracle {
+            ecb: Ecb::new(cipher),
+            suffix,
+        }
+    }
+}
+
+impl<C: BlockCipher> ECBOracle for VariablePrefixECBOracle<C> {
+    fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
+        let mut prefix = vec![0; rand::thread_rng().gen_range(0..=255)];
+        rand::thread_rng().fill_bytes(&mut prefix);
+
+        let message = [&prefix, message.bytes(), self.suffix.bytes()].concat();
+        self.ecb.encrypt(&message)
+    }
+}
+
+impl VariablePrefixECBOracle {
+    pub fn new() -> Self {
+        VariablePrefixECBOracle::default()
+    }
+}
+
+pub struct BasicECBOracle<C: BlockCipher = Aes> {
+    ecb: Ecb<C>,
 }
 
 impl Default for BasicECBOracle {
     /// Returns a new ECBOracle with a randomly generated 128 bit key
     fn default() -> Self {
         let key = aes::generate_16_bit_key();
-        BasicECBOracle { key }
+        Self::with_cipher(Aes::new(&key).expect("key is 16 bytes"))
     }
 }
 
-impl ECBOracle for BasicECBOracle {
+impl<C: BlockCipher> BasicECBOracle<C> {
+    /// Returns a new ECBOracle that encrypts using the supplied block cipher
+    pub fn with_cipher(cipher: C) -> Self {
+        BasicECBOracle {
+            ecb: Ecb::new(cipher),
+        }
+    }
+}
+
+impl<C: BlockCipher> ECBOracle for BasicECBOracle<C> {
     /// Encrypts some plain text using ECB mode
     /// Randomly generates a key
     fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
@@ -226,7 +168,7 @@ impl ECBOracle for BasicECBOracle {
         ).unwrap();
 
         let final_message = [message.bytes(), suffix.bytes()].concat();
-        ecb::encrypt(&self.key, final_message)
+        self.ecb.encrypt(&final_message)
     }
 }
 
@@ -269,16 +211,19 @@ fn surround_with_random_bytes<T: Digest>(message: T) -> Vec<u8> {
 fn random_bytes() -> Vec<u8> {
     let len = rand::thread_rng().gen_range(5..11);
     let mut rand_bytes = [0; 10];
-    ssl_rand::rand_bytes(&mut rand_bytes).expect("encryption_oracle::random_bytes() failed");
+    rand::thread_rng().fill_bytes(&mut rand_bytes);
     rand_bytes[..len].to_vec()
 }
 
+/// Guesses whether an encrypted message was encrypted using aes_ecb, aes_cbc or aes_ctr
+///
+/// Only the likeliest mode is returned, see [ModeClassifier] for how likely each mode is
 pub fn detect_aes_type<T: Digest>(encrypted_message: T) -> AesMode {
-    if detect_aes_ecb(encrypted_message) {
-        AesMode::ECB
-    } else {
-        AesMode::CBC
-    }
+    let (mode, _) = ModeClassifier::new()
+        .with_block_sizes(&[16])
+        .classify(encrypted_message.bytes())
+        .most_likely_mode();
+    mode
 }
 
 /// Scans an encrypted message and guesses if it has been encrypted using aes_ecb with a 128 bit key
@@ -290,9 +235,14 @@ pub fn detect_aes_ecb<T: Digest>(encrypted_message: T) -> bool {
 mod test {
     use std::str::FromStr;
 
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::aes::get_random_bytes;
     use crate::cyphers::aes::oracles::ecb_oracle::{
-        BasicECBOracle, ECBOracle, RandomPrefixECBOracle,
+        BasicECBOracle, ECBOracle, RandomPrefixECBOracle, VariablePrefixECBOracle,
     };
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::cbc::Cbc;
+    use crate::cyphers::modes::ecb::Ecb;
     use crate::encoding::base64::Base64;
     use crate::encoding::Digest;
 
@@ -301,7 +251,7 @@ mod test {
         let oracle = BasicECBOracle::new();
         let expected = Base64::from_str("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK").unwrap().bytes().to_vec();
 
-        let result = oracle.decrypt();
+        let result = oracle.decrypt().unwrap();
 
         assert_eq!(expected, result);
     }
@@ -311,8 +261,76 @@ mod test {
         let oracle = RandomPrefixECBOracle::new();
         let expected = Base64::from_str("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK").unwrap().bytes().to_vec();
 
-        let result = oracle.decrypt();
+        let result = oracle.decrypt().unwrap();
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn eight_byte_block_oracle_decrypts() {
+        let oracle = BasicECBOracle::with_cipher(Feistel::new(&get_random_bytes(16)));
+        let expected = Base64::from_str("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK").unwrap().bytes().to_vec();
+
+        assert_eq!(Some(8), oracle.find_key_length());
+        assert_eq!(expected, oracle.decrypt().unwrap());
+    }
+
+    #[test]
+    fn aes_256_prefixing_oracle_decrypts() {
+        let oracle = RandomPrefixECBOracle::with_cipher(Aes::new(&get_random_bytes(32)).unwrap());
+        let expected = Base64::from_str("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK").unwrap().bytes().to_vec();
+
+        assert_eq!(expected, oracle.decrypt().unwrap());
+    }
+
+    #[test]
+    fn variable_prefix_oracle_decrypts() {
+        let oracle = VariablePrefixECBOracle::new();
+        let expected = Base64::from_str("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK").unwrap().bytes().to_vec();
+
+        assert_eq!(expected, oracle.decrypt().unwrap());
+    }
+
+    /// Repeats blocks in both the prefix and the secret
+    struct RepetitiveOracle {
+        ecb: Ecb<Aes>,
+    }
+
+    impl ECBOracle for RepetitiveOracle {
+        fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
+            let prefix = "YELLOW SUBMARINE".repeat(3) + "x";
+            let secret = "AAAAAAAAAAAAAAAA".repeat(2) + "xxxxxxxxxxxxxxxx and the rest";
+            let message = [prefix.as_bytes(), message.bytes(), secret.as_bytes()].concat();
+            self.ecb.encrypt(&message)
+        }
+    }
+
+    #[test]
+    fn repeated_blocks_do_not_confuse_decryption() {
+        let oracle = RepetitiveOracle {
+            ecb: Ecb::new(Aes::new(&get_random_bytes(16)).unwrap()),
+        };
+        let expected = "AAAAAAAAAAAAAAAA".repeat(2) + "xxxxxxxxxxxxxxxx and the rest";
+
+        assert_eq!(expected.as_bytes(), oracle.decrypt().unwrap());
+    }
+
+    struct CbcOracle {
+        cbc: Cbc<Aes>,
+    }
+
+    impl ECBOracle for CbcOracle {
+        fn encrypt<T: Digest>(&self, message: T) -> Vec<u8> {
+            self.cbc.encrypt(message.bytes(), &[0; 16])
+        }
+    }
+
+    #[test]
+    fn non_ecb_oracle_is_an_error() {
+        let oracle = CbcOracle {
+            cbc: Cbc::new(Aes::new(&get_random_bytes(16)).unwrap()),
+        };
+
+        assert!(oracle.decrypt().is_err());
+    }
 }
@@ -0,0 +1,204 @@
+//! Recovers the secret an ECB oracle appends to its input, one byte at a time
+//!
+//! Every query starts with two marker blocks, one of a random byte x followed by one of another
+//! random byte y, so the secret can't be built to contain them. The pair only
+//! encrypts to E(X) E(Y) when it lands on a block boundary, so finding it in the cipher text
+//! locates the start of the attacker's input however long the oracle's prefix was on that call.
+//! Calls where the pair is missing are retried with a different amount of filler in front, which
+//! handles fixed prefixes of any length as well as prefixes that change length on every call.
+//! Duplicate blocks in the prefix or the secret are never mistaken for the marker, since it is
+//! the only place both blocks appear in order.
+//!
+//! Each byte is recovered with a single aligned query: the input holds the 256 possible blocks
+//! ending in the unknown byte, followed by enough filler to push that byte to the end of a block
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use rand::Rng;
+
+use crate::cyphers::aes::oracles::ecb_oracle::ECBOracle;
+use crate::Error;
+
+const MIN_BLOCK_SIZE: usize = 4;
+const MAX_BLOCK_SIZE: usize = 64;
+/// The number of attempts, per byte of block size, to get the marker onto a block boundary
+const ALIGNMENT_ROUNDS: usize = 32;
+const FILLER: u8 = b'A';
+
+/// Two blocks of a single byte each, with their encryptions
+struct Marker {
+    x: u8,
+    y: u8,
+    encrypted_x: Vec<u8>,
+    encrypted_y: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteAtATimeError {
+    BlockSizeNotFound,
+    MarkerNotIsolated,
+    AlignmentFailed { attempts: usize },
+    NoMatch { position: usize },
+}
+
+impl Display for ByteAtATimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ByteAtATimeError::BlockSizeNotFound => write!(
+                f,
+                "no repeated blocks of {MIN_BLOCK_SIZE} to {MAX_BLOCK_SIZE} bytes, the oracle is \
+                 not using ECB mode"
+            ),
+            ByteAtATimeError::MarkerNotIsolated => {
+                write!(
+                    f,
+                    "could not tell the marker blocks apart from the oracle's own"
+                )
+            }
+            ByteAtATimeError::AlignmentFailed { attempts } => write!(
+                f,
+                "marker blocks were not aligned in any of {attempts} attempts"
+            ),
+            ByteAtATimeError::NoMatch { position } => write!(
+                f,
+                "no candidate matched byte {position}, the oracle is answering inconsistently"
+            ),
+        }
+    }
+}
+
+impl StdError for ByteAtATimeError {}
+
+/// Decrypts the secret the oracle appends to its input, without any padding
+///
+/// Errors
+/// If the oracle fails, is not using ECB mode or changes the secret between calls
+pub fn decrypt<O: ECBOracle + ?Sized>(oracle: &O) -> Result<Vec<u8>, Error> {
+    let block_size = find_block_size(oracle)?;
+    let marker = find_marker(oracle, block_size)?;
+
+    let mut known = Vec::new();
+    loop {
+        let position = known.len();
+        let filler = vec![FILLER; block_size - 1 - position % block_size];
+
+        // the block_size - 1 bytes that come before the unknown byte in its block
+        let context = [vec![FILLER; block_size - 1], known.clone()].concat();
+        let context = &context[context.len() - (block_size - 1)..];
+        let dictionary: Vec<u8> = (0..=255)
+            .flat_map(|guess| [context, &[guess]].concat())
+            .collect();
+
+        let cipher_text =
+            aligned_query(oracle, block_size, &marker, &[dictionary, filler].concat())?;
+        let (dictionary, secret) = cipher_text.split_at(256 * block_size);
+        let target = match secret.chunks(block_size).nth(position / block_size) {
+            Some(target) => target,
+            None => break,
+        };
+
+        match dictionary
+            .chunks(block_size)
+            .position(|block| block == target)
+        {
+            Some(byte) => known.push(byte as u8),
+            // once the secret runs out the first padding byte matches as 0x01, after which the
+            // padding changes to 0x02 0x02 and nothing matches
+            None if known.last() == Some(&1) => {
+                known.pop();
+                break;
+            }
+            None => return Err(Box::new(ByteAtATimeError::NoMatch { position })),
+        }
+    }
+
+    Ok(known)
+}
+
+/// Finds the block size from the smallest blocks that repeat when encrypting a run of one byte
+///
+/// Errors
+/// If the oracle fails or no block size from 4 to 64 bytes gives repeated blocks
+pub fn find_block_size<O: ECBOracle + ?Sized>(oracle: &O) -> Result<usize, Error> {
+    let cipher_text = oracle.try_encrypt(vec![FILLER; 3 * MAX_BLOCK_SIZE])?;
+    (MIN_BLOCK_SIZE..=MAX_BLOCK_SIZE)
+        .filter(|size| cipher_text.len().is_multiple_of(*size))
+        .find(|size| !repeated_blocks(&cipher_text, *size).is_empty())
+        .ok_or_else(|| Box::new(ByteAtATimeError::BlockSizeNotFound) as Error)
+}
+
+/// Picks the marker bytes and learns E(X) and E(Y)
+///
+/// A run of three blocks of a single byte contains at least two whole blocks of it wherever it
+/// lands, and the blocks that repeat for only one of the two bytes must be the marker rather than
+/// part of the prefix or secret
+fn find_marker<O: ECBOracle + ?Sized>(oracle: &O, block_size: usize) -> Result<Marker, Error> {
+    let mut rng = rand::thread_rng();
+    let x = loop {
+        let x = rng.gen();
+        if x != FILLER {
+            break x;
+        }
+    };
+    let y = loop {
+        let y = rng.gen();
+        if y != FILLER && y != x {
+            break y;
+        }
+    };
+
+    let repeated_x = repeated_blocks(&oracle.try_encrypt(vec![x; 3 * block_size])?, block_size);
+    let repeated_y = repeated_blocks(&oracle.try_encrypt(vec![y; 3 * block_size])?, block_size);
+    let only_x: Vec<&Vec<u8>> = repeated_x.difference(&repeated_y).collect();
+    let only_y: Vec<&Vec<u8>> = repeated_y.difference(&repeated_x).collect();
+    match (&only_x[..], &only_y[..]) {
+        ([encrypted_x], [encrypted_y]) => Ok(Marker {
+            x,
+            y,
+            encrypted_x: encrypted_x.to_vec(),
+            encrypted_y: encrypted_y.to_vec(),
+        }),
+        _ => Err(Box::new(ByteAtATimeError::MarkerNotIsolated)),
+    }
+}
+
+/// Sends the payload behind the marker blocks until they are block aligned, returning the cipher
+/// text that follows them
+fn aligned_query<O: ECBOracle + ?Sized>(
+    oracle: &O,
+    block_size: usize,
+    marker: &Marker,
+    payload: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let attempts = ALIGNMENT_ROUNDS * block_size;
+    for attempt in 0..attempts {
+        let stimulus = [
+            vec![FILLER; attempt % block_size],
+            vec![marker.x; block_size],
+            vec![marker.y; block_size],
+            payload.to_vec(),
+        ]
+        .concat();
+        let cipher_text = oracle.try_encrypt(stimulus)?;
+
+        let blocks: Vec<&[u8]> = cipher_text.chunks(block_size).collect();
+        if let Some(start) = blocks.windows(2).position(|pair| {
+            pair[0] == &marker.encrypted_x[..] && pair[1] == &marker.encrypted_y[..]
+        }) {
+            return Ok(cipher_text[(start + 2) * block_size..].to_vec());
+        }
+    }
+
+    Err(Box::new(ByteAtATimeError::AlignmentFailed { attempts }))
+}
+
+/// The blocks that are immediately followed by an identical block
+fn repeated_blocks(cipher_text: &[u8], block_size: usize) -> HashSet<Vec<u8>> {
+    let blocks: Vec<&[u8]> = cipher_text.chunks(block_size).collect();
+    blocks
+        .windows(2)
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0].to_vec())
+        .collect()
+}
@@ -0,0 +1,186 @@
+//! Recovers CTR encrypted plain text from an oracle that lets you rewrite part of a message
+//!
+//! Editing re-encrypts the new text under the same keystream, so editing in zeros hands back the
+//! keystream itself, and xoring it with the original cipher text gives the plain text
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::aes::ctr::{CTRSampleEncryptions, EncryptedMessage};
+use crate::Error;
+
+pub mod http;
+
+#[derive(Debug, PartialEq)]
+enum EditOracleError {
+    OutOfRange {
+        offset: usize,
+        length: usize,
+        message_length: usize,
+    },
+    LengthChanged {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl Display for EditOracleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditOracleError::OutOfRange {
+                offset,
+                length,
+                message_length,
+            } => write!(
+                f,
+                "an edit of {length} bytes at offset {offset} runs past the end of a {message_length} byte message"
+            ),
+            EditOracleError::LengthChanged { expected, actual } => write!(
+                f,
+                "the edited message was {actual} bytes long, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl StdError for EditOracleError {}
+
+pub trait EditOracle {
+    /// Replaces the plain text at the offset with the new text and returns the re-encrypted message
+    fn edit(
+        &self,
+        message: &EncryptedMessage,
+        offset: usize,
+        new_text: &[u8],
+    ) -> Result<EncryptedMessage, Error>;
+
+    /// The longest edit the oracle accepts in a single call, if it is limited at all
+    fn max_edit_length(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl EditOracle for CTRSampleEncryptions {
+    /// Errors
+    /// If the edit runs past the end of the message
+    fn edit(
+        &self,
+        message: &EncryptedMessage,
+        offset: usize,
+        new_text: &[u8],
+    ) -> Result<EncryptedMessage, Error> {
+        let message_length = message.cipher_text.len();
+        if offset > message_length || new_text.len() > message_length - offset {
+            return Err(Box::new(EditOracleError::OutOfRange {
+                offset,
+                length: new_text.len(),
+                message_length,
+            }));
+        }
+
+        Ok(CTRSampleEncryptions::edit(
+            self,
+            message.clone(),
+            offset,
+            &new_text,
+        ))
+    }
+}
+
+/// Recovers the plain text of the message with as few edits as the oracle allows
+///
+/// A single edit covering the whole message is enough when the oracle doesn't limit the edit
+/// length, otherwise the message is edited one `max_edit_length` chunk at a time (byte-at-a-time
+/// for oracles that only accept a single byte)
+///
+/// Errors
+/// If the oracle rejects an edit or returns a message of a different length
+pub fn recover_plain_text<O: EditOracle + ?Sized>(
+    oracle: &O,
+    message: &EncryptedMessage,
+) -> Result<Vec<u8>, Error> {
+    let cipher_text = &message.cipher_text;
+    let chunk_length = oracle.max_edit_length().unwrap_or(cipher_text.len()).max(1);
+
+    let mut key_stream = Vec::with_capacity(cipher_text.len());
+    for offset in (0..cipher_text.len()).step_by(chunk_length) {
+        let length = chunk_length.min(cipher_text.len() - offset);
+        let edited = oracle.edit(message, offset, &vec![0; length])?;
+        if edited.cipher_text.len() != cipher_text.len() {
+            return Err(Box::new(EditOracleError::LengthChanged {
+                expected: cipher_text.len(),
+                actual: edited.cipher_text.len(),
+            }));
+        }
+        key_stream.extend_from_slice(&edited.cipher_text[offset..offset + length]);
+    }
+
+    Ok(cipher_text
+        .iter()
+        .zip(key_stream.iter())
+        .map(|(x, y)| x ^ y)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::ctr::{CTRSampleEncryptions, EncryptedMessage};
+    use crate::cyphers::aes::oracles::edit_oracle::{recover_plain_text, EditOracle};
+    use crate::cyphers::aes::oracles::instrumented::Instrumented;
+    use crate::Error;
+
+    const PLAIN_TEXT: &[u8] = b"I'm back and I'm ringin' the bell, a rockin' on the mike";
+
+    /// Rejects any edit longer than its limit
+    struct LimitedOracle {
+        oracle: CTRSampleEncryptions,
+        limit: usize,
+    }
+
+    impl EditOracle for LimitedOracle {
+        fn edit(
+            &self,
+            message: &EncryptedMessage,
+            offset: usize,
+            new_text: &[u8],
+        ) -> Result<EncryptedMessage, Error> {
+            assert!(new_text.len() <= self.limit);
+            EditOracle::edit(&self.oracle, message, offset, new_text)
+        }
+
+        fn max_edit_length(&self) -> Option<usize> {
+            Some(self.limit)
+        }
+    }
+
+    #[test]
+    fn recovers_with_a_single_edit() {
+        let oracle = Instrumented::new(CTRSampleEncryptions::new());
+        let message = oracle.inner().encrypt(&PLAIN_TEXT);
+
+        assert_eq!(recover_plain_text(&oracle, &message).unwrap(), PLAIN_TEXT);
+        assert_eq!(oracle.stats().calls, 1);
+    }
+
+    #[test]
+    fn recovers_with_limited_edits() {
+        for limit in [1, 16] {
+            let oracle = Instrumented::new(LimitedOracle {
+                oracle: CTRSampleEncryptions::new(),
+                limit,
+            });
+            let message = oracle.inner().oracle.encrypt(&PLAIN_TEXT);
+
+            assert_eq!(recover_plain_text(&oracle, &message).unwrap(), PLAIN_TEXT);
+            assert_eq!(oracle.stats().calls, PLAIN_TEXT.len().div_ceil(limit));
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_edits() {
+        let oracle = CTRSampleEncryptions::new();
+        let message = oracle.encrypt(&PLAIN_TEXT);
+
+        assert!(EditOracle::edit(&oracle, &message, PLAIN_TEXT.len() - 1, &[0; 2]).is_err());
+        assert!(EditOracle::edit(&oracle, &message, PLAIN_TEXT.len() + 1, &[]).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! An edit oracle reached over HTTP, such as the `/ctr-edit` route of `simple-server`
+//!
+//! Messages are sent as tokens, the hex encoded big endian nonce followed by the cipher text. An
+//! edit sends the `token`, the `offset` and the hex encoded `text`, and a 200 response carries the
+//! edited token in its body; network errors, 429 and 5xx responses are retried and any other
+//! status is an error.
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::cyphers::aes::ctr::EncryptedMessage;
+use crate::cyphers::aes::oracles::edit_oracle::EditOracle;
+use crate::cyphers::aes::oracles::http::{self, RetryingClient};
+use crate::encoding::hex::Hex;
+use crate::encoding::Digest;
+use crate::Error;
+
+const NONCE_LENGTH: usize = 8;
+/// The longest edit `simple-server` accepts in a single request
+const DEFAULT_MAX_EDIT_LENGTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+enum TokenError {
+    TooShort(usize),
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::TooShort(length) => write!(
+                f,
+                "a token of {length} bytes is too short to hold the {NONCE_LENGTH} byte nonce"
+            ),
+        }
+    }
+}
+
+impl StdError for TokenError {}
+
+/// Encodes the message as the hex encoded big endian nonce followed by the cipher text
+pub fn encode_token(message: &EncryptedMessage) -> String {
+    Hex::new(&[&message.nonce.to_be_bytes()[..], &message.cipher_text].concat()).to_string()
+}
+
+/// Errors
+/// If the token is too short to hold a nonce
+pub fn decode_token(token: &str) -> Result<EncryptedMessage, Error> {
+    let token = Hex::from_str(token)?;
+    let token = token.bytes();
+    if token.len() < NONCE_LENGTH {
+        return Err(Box::new(TokenError::TooShort(token.len())));
+    }
+
+    let (nonce, cipher_text) = token.split_at(NONCE_LENGTH);
+    Ok(EncryptedMessage {
+        cipher_text: cipher_text.to_vec(),
+        nonce: u64::from_be_bytes(nonce.try_into()?),
+    })
+}
+
+pub struct HttpEditOracle {
+    client: RetryingClient,
+    url: String,
+    max_edit_length: Option<usize>,
+}
+
+impl HttpEditOracle {
+    /// Queries the endpoint at the url, e.g. `http://127.0.0.1:8080/ctr-edit`
+    ///
+    /// By default failed requests are retried three times, requests are not rate limited and
+    /// edits are at most 16 bytes long, the limit of `simple-server`
+    pub fn new(url: &str) -> HttpEditOracle {
+        HttpEditOracle {
+            client: RetryingClient::new(),
+            url: url.to_string(),
+            max_edit_length: Some(DEFAULT_MAX_EDIT_LENGTH),
+        }
+    }
+
+    /// Retries an edit that fails with a network error, 429 or 5xx response up to `retries`
+    /// times, first after `retry_delay` and then twice as long as the time before
+    pub fn with_retries(mut self, retries: usize, retry_delay: Duration) -> HttpEditOracle {
+        self.client.set_retries(retries, retry_delay);
+        self
+    }
+
+    /// Starts each edit request no sooner than `min_interval` after the one before
+    pub fn with_min_interval(mut self, min_interval: Duration) -> HttpEditOracle {
+        self.client.set_min_interval(min_interval);
+        self
+    }
+
+    /// The longest edit the server accepts, none if it accepts edits of any length
+    pub fn with_max_edit_length(mut self, max_edit_length: Option<usize>) -> HttpEditOracle {
+        self.max_edit_length = max_edit_length;
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl EditOracle for HttpEditOracle {
+    /// Errors
+    /// If the server responds with a status other than 200, cannot be reached after every retry
+    /// or returns a malformed token
+    fn edit(
+        &self,
+        message: &EncryptedMessage,
+        offset: usize,
+        new_text: &[u8],
+    ) -> Result<EncryptedMessage, Error> {
+        let token = encode_token(message);
+        let offset = offset.to_string();
+        let text = Hex::new(new_text).to_string();
+        let response = self.client.get(
+            &self.url,
+            &[("token", &token), ("offset", &offset), ("text", &text)],
+        )?;
+
+        match response.status() {
+            StatusCode::OK => decode_token(response.text()?.trim()),
+            status => Err(http::unexpected_status(status)),
+        }
+    }
+
+    fn max_edit_length(&self) -> Option<usize> {
+        self.max_edit_length
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::cyphers::aes::ctr::EncryptedMessage;
+    use crate::cyphers::aes::oracles::edit_oracle::http::{
+        decode_token, encode_token, HttpEditOracle,
+    };
+    use crate::cyphers::aes::oracles::edit_oracle::EditOracle;
+    use crate::cyphers::aes::oracles::http::test::serve;
+
+    #[test]
+    fn round_trips_tokens() {
+        let message = EncryptedMessage {
+            cipher_text: vec![0xaa, 0xbb],
+            nonce: 7,
+        };
+        let token = encode_token(&message);
+        assert_eq!(token, "0000000000000007aabb");
+
+        let decoded = decode_token(&token).unwrap();
+        assert_eq!(decoded.cipher_text, message.cipher_text);
+        assert_eq!(decoded.nonce, message.nonce);
+        assert!(decode_token("00").is_err());
+    }
+
+    #[test]
+    fn edits_and_retries_transient_failures() {
+        let address = serve(
+            "GET /ctr-edit?token=0000000000000007aabb&offset=1&text=00",
+            &[
+                ("200 OK", "0000000000000007aacc"),
+                ("503 Service Unavailable", ""),
+                ("200 OK", "0000000000000007aadd"),
+                ("413 Payload Too Large", ""),
+            ],
+        );
+        let oracle = HttpEditOracle::new(&format!("{address}/ctr-edit"))
+            .with_retries(1, Duration::from_millis(1));
+        let message = EncryptedMessage {
+            cipher_text: vec![0xaa, 0xbb],
+            nonce: 7,
+        };
+
+        let edited = oracle.edit(&message, 1, &[0]).unwrap();
+        assert_eq!(edited.cipher_text, vec![0xaa, 0xcc]);
+        let retried = oracle.edit(&message, 1, &[0]).unwrap();
+        assert_eq!(retried.cipher_text, vec![0xaa, 0xdd]);
+        assert!(oracle.edit(&message, 1, &[0]).is_err());
+    }
+}
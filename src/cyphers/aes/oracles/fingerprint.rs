@@ -0,0 +1,418 @@
+//! Works out how an encryption oracle lays out its plain text, in as few queries as possible
+//!
+//! Given any oracle that encrypts a chosen input between a secret prefix and suffix:
+//! - the block size is where the cipher text first grows as the input gets longer, found with an
+//!   exponential then a binary search; a stream mode grows by a single byte
+//! - whether the IV or nonce is fixed is whether the same input encrypts the same way twice
+//! - when it is, the prefix length is where the cipher text starts to differ when a byte of the
+//!   input changes, binary searching for the first byte that moves into the next block
+//! - for ECB, a block of PKCS#7 padding placed where the padding should land shows whether the
+//!   oracle pads with PKCS#7; other modes chain the padding block to the one before, so this
+//!   can't be told
+//! - the suffix length is what is left of the fixed length once the prefix is taken away
+use std::cell::Cell;
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::Error;
+
+/// The longest input tried while looking for the block size
+const MAX_BLOCK_SIZE: usize = 256;
+const FILLER: u8 = b'A';
+const CHANGED: u8 = b'B';
+
+#[derive(Debug, PartialEq)]
+enum FingerprintError {
+    BlockSizeNotFound,
+    InconsistentLength { expected: usize, actual: usize },
+    InputIgnored,
+    InconsistentLayout,
+}
+
+impl Display for FingerprintError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FingerprintError::BlockSizeNotFound => write!(
+                f,
+                "cipher text length never changed for inputs up to {MAX_BLOCK_SIZE} bytes"
+            ),
+            FingerprintError::InconsistentLength { expected, actual } => write!(
+                f,
+                "the same input encrypted to {expected} and then {actual} bytes, the oracle's prefix or suffix changes length"
+            ),
+            FingerprintError::InputIgnored => {
+                write!(f, "changing the input didn't change the cipher text")
+            }
+            FingerprintError::InconsistentLayout => write!(
+                f,
+                "the cipher text lengths can't come from a prefix and suffix of fixed lengths"
+            ),
+        }
+    }
+}
+
+impl StdError for FingerprintError {}
+
+/// How an oracle lays out the plain text it encrypts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// None for a stream mode, which never pads
+    pub block_size: Option<usize>,
+    /// The number of cipher text bytes before the input, including any header such as an IV,
+    /// known only if the IV or nonce is fixed
+    pub prefix_length: Option<usize>,
+    /// The number of bytes after the input, known only if the prefix length is; assumes PKCS#7
+    /// padding for block modes unless it has been ruled out, in which case no padding is added
+    /// to a whole number of blocks
+    pub suffix_length: Option<usize>,
+    /// Whether the oracle pads with PKCS#7, known only for ECB and stream modes
+    pub pkcs7: Option<bool>,
+    /// Whether the same input always encrypts to the same cipher text, as it does with a fixed IV
+    /// or nonce, or with ECB which has neither
+    pub fixed_iv: bool,
+    /// The number of times the oracle was queried
+    pub queries: usize,
+}
+
+/// Fingerprints the oracle, which encrypts its input between a prefix and a suffix of fixed
+/// lengths
+///
+/// Errors
+/// If the prefix or suffix change length between queries, the cipher text doesn't grow with the
+/// input or doesn't depend on it
+pub fn fingerprint<F: Fn(&[u8]) -> Vec<u8>>(oracle: F) -> Result<Fingerprint, Error> {
+    try_fingerprint(|input| Ok(oracle(input)))
+}
+
+/// Fingerprints an oracle that can fail or refuse to answer, such as one with a query budget
+///
+/// Errors
+/// If the oracle fails, or for any of the reasons [fingerprint] does
+pub fn try_fingerprint<F: Fn(&[u8]) -> Result<Vec<u8>, Error>>(
+    oracle: F,
+) -> Result<Fingerprint, Error> {
+    let queries = Cell::new(0);
+    let encrypt = |input: &[u8]| {
+        queries.set(queries.get() + 1);
+        oracle(input)
+    };
+
+    let empty = encrypt(&[])?;
+    let length = |input_length: usize| Ok(encrypt(&filler(input_length))?.len());
+
+    let (input_length, growth) = first_growth(empty.len(), length)?;
+    let block_size = if growth == 1 { None } else { Some(growth) };
+    let granularity = block_size.unwrap_or(1);
+
+    // one byte longer than a block, so that a changed byte can be moved into the next block
+    let base = filler(granularity + 1);
+    let cipher_text = encrypt(&base)?;
+    let repeated = encrypt(&base)?;
+    if cipher_text.len() != repeated.len() {
+        return Err(Box::new(FingerprintError::InconsistentLength {
+            expected: cipher_text.len(),
+            actual: repeated.len(),
+        }));
+    }
+    // an oracle whose prefix or suffix changes length can still answer the same way twice in a
+    // row, so check that the empty input hasn't changed length since the first query
+    let empty_length = encrypt(&[])?.len();
+    if empty_length != empty.len() {
+        return Err(Box::new(FingerprintError::InconsistentLength {
+            expected: empty.len(),
+            actual: empty_length,
+        }));
+    }
+    let fixed_iv = cipher_text == repeated;
+
+    let prefix_length = if fixed_iv {
+        Some(find_prefix_length(encrypt, &cipher_text, granularity)?)
+    } else {
+        None
+    };
+
+    // PKCS#7 always adds padding, so an oracle whose cipher text grows too soon for that doesn't
+    // use it
+    let padded_length = empty.len().checked_sub(input_length);
+    let pkcs7 = match (block_size, prefix_length, padded_length) {
+        (None, _, _) | (Some(_), _, None) => Some(false),
+        (Some(block_size), Some(prefix_length), Some(padded_length)) => {
+            is_ecb_pkcs7(encrypt, block_size, prefix_length, padded_length)?
+        }
+        (Some(_), None, Some(_)) => None,
+    };
+
+    let fixed_length = match (block_size, pkcs7, padded_length) {
+        (None, _, _) => Some(empty.len()),
+        (Some(_), Some(false), _) => (empty.len() + 1).checked_sub(input_length),
+        (Some(_), _, padded_length) => padded_length,
+    }
+    .ok_or(FingerprintError::InconsistentLayout)?;
+    let suffix_length = prefix_length
+        .map(|prefix_length| {
+            fixed_length
+                .checked_sub(prefix_length)
+                .ok_or(FingerprintError::InconsistentLayout)
+        })
+        .transpose()?;
+
+    Ok(Fingerprint {
+        block_size,
+        prefix_length,
+        suffix_length,
+        pkcs7,
+        fixed_iv,
+        queries: queries.get(),
+    })
+}
+
+/// The shortest input that makes the cipher text longer than it is for an empty input, and how
+/// much longer it becomes
+fn first_growth<L: Fn(usize) -> Result<usize, Error>>(
+    empty_length: usize,
+    length: L,
+) -> Result<(usize, usize), Error> {
+    let grown = |input_length: usize| -> Result<Option<usize>, Error> {
+        let new_length = length(input_length)?;
+        if new_length < empty_length {
+            return Err(Box::new(FingerprintError::InconsistentLength {
+                expected: empty_length,
+                actual: new_length,
+            }));
+        }
+        Ok(Some(new_length - empty_length).filter(|growth| *growth > 0))
+    };
+
+    // double the input until the cipher text grows, then narrow down where it first did
+    let mut upper = 1;
+    let mut growth = loop {
+        if let Some(growth) = grown(upper)? {
+            break growth;
+        }
+        if upper >= MAX_BLOCK_SIZE {
+            return Err(Box::new(FingerprintError::BlockSizeNotFound));
+        }
+        upper *= 2;
+    };
+    let mut lower = upper / 2;
+    while upper - lower > 1 {
+        let middle = (lower + upper) / 2;
+        match grown(middle)? {
+            Some(middle_growth) => {
+                upper = middle;
+                growth = middle_growth;
+            }
+            None => lower = middle,
+        }
+    }
+
+    Ok((upper, growth))
+}
+
+/// The number of bytes before the input, for an oracle with a fixed IV or nonce
+///
+/// Changing a byte of the input changes the cipher text from the block it lands in onwards, so
+/// the prefix ends where moving the changed byte along first moves it into the next block
+fn find_prefix_length<E: Fn(&[u8]) -> Result<Vec<u8>, Error>>(
+    encrypt: E,
+    cipher_text: &[u8],
+    granularity: usize,
+) -> Result<usize, Error> {
+    let changed_block = |position: usize| -> Result<usize, Error> {
+        let mut input = filler(granularity + 1);
+        input[position] = CHANGED;
+        first_difference(cipher_text, &encrypt(&input)?)
+            .map(|index| index / granularity)
+            .ok_or_else(|| Box::new(FingerprintError::InputIgnored) as Error)
+    };
+
+    let first_block = changed_block(0)?;
+    if granularity == 1 {
+        return Ok(first_block);
+    }
+
+    // the changed byte reaches the next block somewhere in 1..=granularity
+    let (mut lower, mut upper) = (0, granularity);
+    while upper - lower > 1 {
+        let middle = (lower + upper) / 2;
+        if changed_block(middle)? > first_block {
+            upper = middle;
+        } else {
+            lower = middle;
+        }
+    }
+
+    Ok((first_block + 1) * granularity - upper)
+}
+
+/// Whether an ECB oracle pads with PKCS#7, none if the oracle is not using ECB
+///
+/// Two blocks of full PKCS#7 padding are placed on a block boundary, followed by enough input
+/// that PKCS#7 would pad with a whole block; ECB encrypts the two blocks the same, and the final
+/// block the same as them if the padding is PKCS#7
+fn is_ecb_pkcs7<E: Fn(&[u8]) -> Result<Vec<u8>, Error>>(
+    encrypt: E,
+    block_size: usize,
+    prefix_length: usize,
+    fixed_length: usize,
+) -> Result<Option<bool>, Error> {
+    let padding_byte = match u8::try_from(block_size) {
+        Ok(padding_byte) => padding_byte,
+        Err(_) => return Ok(None),
+    };
+    let alignment = (block_size - prefix_length % block_size) % block_size;
+    let used = fixed_length + alignment + 2 * block_size;
+    let trailing = (block_size - used % block_size) % block_size;
+
+    let input = [
+        filler(alignment),
+        vec![padding_byte; 2 * block_size],
+        filler(trailing),
+    ]
+    .concat();
+    let cipher_text = encrypt(&input)?;
+
+    let start = prefix_length + alignment;
+    let padding_block = cipher_text.get(start..start + block_size);
+    let next_block = cipher_text.get(start + block_size..start + 2 * block_size);
+    Ok(match (padding_block, next_block) {
+        (Some(padding_block), Some(next_block)) if padding_block == next_block => {
+            Some(cipher_text.ends_with(padding_block))
+        }
+        _ => None,
+    })
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .or_else(|| (a.len() != b.len()).then_some(a.len().min(b.len())))
+}
+
+fn filler(length: usize) -> Vec<u8> {
+    vec![FILLER; length]
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::aes::oracles::fingerprint::{fingerprint, Fingerprint};
+    use crate::cyphers::aes::{ctr, generate_16_bit_key, get_random_bytes};
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::cbc::Cbc;
+    use crate::cyphers::modes::ecb::Ecb;
+    use crate::cyphers::padding::zero::ZeroPadding;
+
+    fn surround(prefix_length: usize, input: &[u8], suffix_length: usize) -> Vec<u8> {
+        [
+            &vec![b'p'; prefix_length][..],
+            input,
+            &vec![b's'; suffix_length],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn fingerprints_ecb() {
+        for (prefix_length, suffix_length) in [(0, 0), (7, 20), (16, 16), (37, 3)] {
+            let ecb = Ecb::new(Aes::new(&generate_16_bit_key()).unwrap());
+            let fingerprint =
+                fingerprint(|input| ecb.encrypt(&surround(prefix_length, input, suffix_length)))
+                    .unwrap();
+
+            let expected = Fingerprint {
+                block_size: Some(16),
+                prefix_length: Some(prefix_length),
+                suffix_length: Some(suffix_length),
+                pkcs7: Some(true),
+                fixed_iv: true,
+                queries: fingerprint.queries,
+            };
+            assert_eq!(expected, fingerprint);
+            assert!(fingerprint.queries <= 20, "{}", fingerprint.queries);
+        }
+    }
+
+    #[test]
+    fn fingerprints_unpadded_ecb_with_small_blocks() {
+        let ecb = Ecb::with_padding(Feistel::new(b"key"), ZeroPadding);
+        let framed = fingerprint(|input| ecb.encrypt(&surround(5, input, 9))).unwrap();
+
+        assert_eq!(Some(8), framed.block_size);
+        assert_eq!(Some(5), framed.prefix_length);
+        assert_eq!(Some(9), framed.suffix_length);
+        assert_eq!(Some(false), framed.pkcs7);
+
+        let ecb = Ecb::with_padding(Aes::new(&generate_16_bit_key()).unwrap(), ZeroPadding);
+        let bare = fingerprint(|input| ecb.encrypt(input)).unwrap();
+        assert_eq!(Some(16), bare.block_size);
+        assert_eq!(Some(0), bare.prefix_length);
+        assert_eq!(Some(0), bare.suffix_length);
+        assert_eq!(Some(false), bare.pkcs7);
+    }
+
+    #[test]
+    fn fingerprints_cbc() {
+        let cbc = Cbc::new(Aes::new(&generate_16_bit_key()).unwrap());
+        let iv = generate_16_bit_key();
+        let fixed = fingerprint(|input| cbc.encrypt(&surround(21, input, 30), &iv)).unwrap();
+
+        assert_eq!(Some(16), fixed.block_size);
+        assert_eq!(Some(21), fixed.prefix_length);
+        assert_eq!(Some(30), fixed.suffix_length);
+        assert_eq!(None, fixed.pkcs7);
+        assert!(fixed.fixed_iv);
+
+        let random = fingerprint(|input| {
+            let iv = generate_16_bit_key();
+            [&iv[..], &cbc.encrypt(&surround(21, input, 30), &iv)].concat()
+        })
+        .unwrap();
+
+        assert_eq!(Some(16), random.block_size);
+        assert_eq!(None, random.prefix_length);
+        assert!(!random.fixed_iv);
+    }
+
+    #[test]
+    fn fingerprints_ctr() {
+        let key = generate_16_bit_key();
+        let fixed =
+            fingerprint(|input| ctr::encrypt(&surround(5, input, 3), &key, 0).cipher_text).unwrap();
+
+        let expected = Fingerprint {
+            block_size: None,
+            prefix_length: Some(5),
+            suffix_length: Some(3),
+            pkcs7: Some(false),
+            fixed_iv: true,
+            queries: fixed.queries,
+        };
+        assert_eq!(expected, fixed);
+
+        let random = fingerprint(|input| {
+            ctr::encrypt(&surround(5, input, 3), &key, rand::random()).cipher_text
+        })
+        .unwrap();
+        assert_eq!(None, random.block_size);
+        assert!(!random.fixed_iv);
+    }
+
+    #[test]
+    fn rejects_unusable_oracles() {
+        let ecb = Ecb::new(Aes::new(&generate_16_bit_key()).unwrap());
+        let queries = Cell::new(0);
+        let alternating = fingerprint(|input| {
+            queries.set(queries.get() + 1);
+            let prefix_length = if queries.get() % 2 == 0 { 3 } else { 20 };
+            ecb.encrypt(&surround(prefix_length, input, 5))
+        });
+        assert!(alternating.is_err());
+
+        let constant = get_random_bytes(32);
+        assert!(fingerprint(|_| constant.clone()).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Plumbing shared by the oracles reached over HTTP
+//!
+//! Network errors, 429 and 5xx responses are treated as transient and retried with a doubling
+//! delay; every other response is handed back for the oracle to interpret
+use std::cell::Cell;
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+
+use crate::Error;
+
+const DEFAULT_RETRIES: usize = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+enum HttpOracleError {
+    UnexpectedStatus(StatusCode),
+    RetriesExhausted { attempts: usize, last_error: String },
+}
+
+impl Display for HttpOracleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpOracleError::UnexpectedStatus(status) => {
+                write!(f, "oracle responded with unexpected status {status}")
+            }
+            HttpOracleError::RetriesExhausted {
+                attempts,
+                last_error,
+            } => write!(
+                f,
+                "oracle did not answer after {attempts} attempts, last error: {last_error}"
+            ),
+        }
+    }
+}
+
+impl StdError for HttpOracleError {}
+
+/// The error for a response an oracle doesn't know how to interpret
+pub(crate) fn unexpected_status(status: StatusCode) -> Error {
+    Box::new(HttpOracleError::UnexpectedStatus(status))
+}
+
+/// A blocking client that retries transient failures and limits its request rate
+///
+/// By default failed requests are retried three times and requests are not rate limited
+pub(crate) struct RetryingClient {
+    client: Client,
+    retries: usize,
+    retry_delay: Duration,
+    min_interval: Duration,
+    last_request: Cell<Option<Instant>>,
+}
+
+impl RetryingClient {
+    pub(crate) fn new() -> RetryingClient {
+        RetryingClient {
+            client: Client::new(),
+            retries: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            min_interval: Duration::ZERO,
+            last_request: Cell::new(None),
+        }
+    }
+
+    /// Sets how many times a request is retried, and the delay before the first retry, which
+    /// doubles for each one after
+    pub(crate) fn set_retries(&mut self, retries: usize, retry_delay: Duration) {
+        self.retries = retries;
+        self.retry_delay = retry_delay;
+    }
+
+    /// Waits at least the interval between the start of each request
+    pub(crate) fn set_min_interval(&mut self, min_interval: Duration) {
+        self.min_interval = min_interval;
+    }
+
+    /// Sends a GET request, returning the first response that isn't a transient failure
+    ///
+    /// Errors
+    /// If every attempt failed with a network error, 429 or 5xx response
+    pub(crate) fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<Response, Error> {
+        let mut delay = self.retry_delay;
+        let mut last_error = String::new();
+
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            self.wait_for_rate_limit();
+
+            match self.client.get(url).query(query).send() {
+                Ok(response)
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error() =>
+                {
+                    last_error = format!("status {}", response.status());
+                }
+                Ok(response) => return Ok(response),
+                Err(e) => last_error = e.to_string(),
+            }
+        }
+
+        Err(Box::new(HttpOracleError::RetriesExhausted {
+            attempts: self.retries + 1,
+            last_error,
+        }))
+    }
+
+    fn wait_for_rate_limit(&self) {
+        if let Some(last_request) = self.last_request.get() {
+            let elapsed = last_request.elapsed();
+            if elapsed < self.min_interval {
+                thread::sleep(self.min_interval - elapsed);
+            }
+        }
+        self.last_request.set(Some(Instant::now()));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves one canned status and body per connection, checking each request line starts with
+    /// the expected request, and returns the address to reach it
+    pub(crate) fn serve(
+        expected_request: &'static str,
+        responses: &'static [(&'static str, &'static str)],
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for (stream, (status, body)) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+                assert!(request_line.starts_with(expected_request), "{request_line}");
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        address
+    }
+}
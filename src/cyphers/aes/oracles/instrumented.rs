@@ -0,0 +1,205 @@
+//! Counts the queries an attack makes against an oracle, and optionally limits them
+use std::cell::Cell;
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+use crate::cyphers::aes::ctr::EncryptedMessage;
+use crate::cyphers::aes::oracles::ecb_oracle::ECBOracle;
+use crate::cyphers::aes::oracles::edit_oracle::EditOracle;
+use crate::cyphers::aes::oracles::padding_oracle::PaddingOracle;
+use crate::encoding::Digest;
+use crate::Error;
+
+#[derive(Debug, PartialEq)]
+enum InstrumentedError {
+    BudgetExceeded(usize),
+}
+
+impl Display for InstrumentedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstrumentedError::BudgetExceeded(budget) => {
+                write!(f, "query budget of {budget} exceeded")
+            }
+        }
+    }
+}
+
+impl StdError for InstrumentedError {}
+
+/// What an attack has spent querying an oracle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    /// The number of queries answered by the oracle
+    pub calls: usize,
+    /// The total length of everything submitted to the oracle
+    pub bytes: usize,
+    /// The wall time spent waiting for the oracle to answer
+    pub elapsed: Duration,
+}
+
+/// Wraps an oracle, recording every query made through it
+///
+/// Implements the same oracle traits as the oracle it wraps so it can be handed to any attack in
+/// place of the original. Once a budget is set, queries beyond it are refused with an error
+/// rather than passed on
+pub struct Instrumented<O> {
+    oracle: O,
+    budget: Option<usize>,
+    stats: Cell<QueryStats>,
+}
+
+impl<O> Instrumented<O> {
+    pub fn new(oracle: O) -> Instrumented<O> {
+        Instrumented {
+            oracle,
+            budget: None,
+            stats: Cell::new(QueryStats::default()),
+        }
+    }
+
+    /// Refuses any query after the first `budget` queries
+    pub fn with_budget(mut self, budget: usize) -> Instrumented<O> {
+        self.budget = Some(budget);
+        self
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        self.stats.get()
+    }
+
+    /// The number of queries left before the budget is exceeded, if there is a budget
+    pub fn remaining(&self) -> Option<usize> {
+        self.budget
+            .map(|budget| budget.saturating_sub(self.stats.get().calls))
+    }
+
+    /// Clears the recorded stats, restoring the full budget
+    pub fn reset(&self) {
+        self.stats.set(QueryStats::default());
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.oracle
+    }
+
+    pub fn into_inner(self) -> O {
+        self.oracle
+    }
+
+    /// Runs a single query of the given length against the oracle if the budget allows it
+    fn record<R>(&self, bytes: usize, query: impl FnOnce(&O) -> R) -> Result<R, Error> {
+        let mut stats = self.stats.get();
+        if let Some(budget) = self.budget {
+            if stats.calls >= budget {
+                return Err(Box::new(InstrumentedError::BudgetExceeded(budget)));
+            }
+        }
+
+        let start = Instant::now();
+        let result = query(&self.oracle);
+        stats.calls += 1;
+        stats.bytes += bytes;
+        stats.elapsed += start.elapsed();
+        self.stats.set(stats);
+
+        Ok(result)
+    }
+}
+
+impl<O: ECBOracle> ECBOracle for Instrumented<O> {
+    /// Panics
+    /// If the query budget has been exceeded, use [ECBOracle::try_encrypt] to handle this
+    fn encrypt<T: Digest>(&self, stimulus: T) -> Vec<u8> {
+        self.try_encrypt(stimulus)
+            .expect("oracle query budget exceeded")
+    }
+
+    fn try_encrypt<T: Digest>(&self, stimulus: T) -> Result<Vec<u8>, Error> {
+        let length = stimulus.bytes().len();
+        self.record(length, |oracle| oracle.try_encrypt(stimulus))?
+    }
+}
+
+impl<O: PaddingOracle> PaddingOracle for Instrumented<O> {
+    fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error> {
+        self.record(cipher_text.len() + iv.len(), |oracle| {
+            oracle.is_valid_padding(cipher_text, iv)
+        })?
+    }
+}
+
+impl<O: EditOracle> EditOracle for Instrumented<O> {
+    fn edit(
+        &self,
+        message: &EncryptedMessage,
+        offset: usize,
+        new_text: &[u8],
+    ) -> Result<EncryptedMessage, Error> {
+        self.record(new_text.len(), |oracle| {
+            oracle.edit(message, offset, new_text)
+        })?
+    }
+
+    fn max_edit_length(&self) -> Option<usize> {
+        self.oracle.max_edit_length()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::oracles::ecb_oracle::{BasicECBOracle, ECBOracle};
+    use crate::cyphers::aes::oracles::instrumented::Instrumented;
+    use crate::cyphers::aes::oracles::padding_oracle::{
+        PaddingOracle, PaddingOracleAttack, SamplePaddingOracle,
+    };
+
+    #[test]
+    fn counts_padding_oracle_queries() {
+        let oracle = Instrumented::new(SamplePaddingOracle::new());
+        let encrypted = oracle.inner().encrypt_rand();
+
+        let decryption = PaddingOracleAttack::new(16)
+            .decrypt(&oracle, &encrypted.cipher_text, &encrypted.iv)
+            .unwrap();
+
+        let stats = oracle.stats();
+        assert_eq!(decryption.total_queries(), stats.calls);
+        assert_eq!(stats.calls * 32, stats.bytes);
+        assert_eq!(None, oracle.remaining());
+    }
+
+    #[test]
+    fn budget_is_enforced() {
+        let oracle = Instrumented::new(SamplePaddingOracle::new()).with_budget(100);
+        let encrypted = oracle.inner().encrypt_rand();
+
+        assert!(oracle.decrypt(&encrypted).is_err());
+        assert_eq!(100, oracle.stats().calls);
+        assert_eq!(Some(0), oracle.remaining());
+
+        oracle.reset();
+        assert_eq!(Some(100), oracle.remaining());
+    }
+
+    #[test]
+    fn counts_ecb_oracle_queries() {
+        let oracle = Instrumented::new(BasicECBOracle::new()).with_budget(2);
+
+        oracle.encrypt(b"sixteen bytes!!!".to_vec());
+        assert!(oracle.try_encrypt(b"four".to_vec()).is_ok());
+        assert!(oracle.try_encrypt(b"more".to_vec()).is_err());
+        assert_eq!(20, oracle.stats().bytes);
+    }
+
+    #[test]
+    fn exceeding_the_budget_fails_ecb_attacks() {
+        let oracle = Instrumented::new(BasicECBOracle::new()).with_budget(3);
+
+        assert_eq!(None, oracle.find_key_length());
+        oracle.reset();
+        assert!(oracle.decrypt().is_err());
+        assert_eq!(Some(0), oracle.remaining());
+    }
+}
@@ -0,0 +1,143 @@
+//! Recovers an AES-128 CBC key that is also used as the iv
+//!
+//! Sending C1 || 0 || C1 makes the first plain text block D(C1) ^ K and the third D(C1) ^ 0, so
+//! the two xor to the key. All it takes is an oracle that reveals the plain text when it rejects
+//! a message, e.g. by complaining that it is not valid ascii
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::block_cipher::BlockCipher;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Why the key could not be recovered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyIsIvError {
+    /// The cipher text must be at least three blocks long
+    TooShort { length: usize },
+    /// The oracle accepted the modified cipher text without revealing its plain text
+    NoPlainTextRevealed,
+    /// The revealed plain text was not the three blocks that were sent
+    UnexpectedPlainText { length: usize },
+    /// The recovered key does not encrypt back to the first cipher text block, so the iv is not
+    /// the key
+    KeyNotConfirmed,
+}
+
+impl Display for KeyIsIvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyIsIvError::TooShort { length } => write!(
+                f,
+                "cipher text is {length} bytes but at least {} are needed",
+                3 * BLOCK_SIZE
+            ),
+            KeyIsIvError::NoPlainTextRevealed => {
+                write!(f, "oracle did not reveal the plain text")
+            }
+            KeyIsIvError::UnexpectedPlainText { length } => write!(
+                f,
+                "oracle revealed {length} bytes of plain text instead of {}",
+                3 * BLOCK_SIZE
+            ),
+            KeyIsIvError::KeyNotConfirmed => {
+                write!(
+                    f,
+                    "recovered key does not re-encrypt the cipher text, the iv is not the key"
+                )
+            }
+        }
+    }
+}
+
+impl StdError for KeyIsIvError {}
+
+/// Recovers the key from a cipher text encrypted with the key as the iv
+///
+/// The decrypt function models the oracle's error channel: it returns the plain text as an error
+/// when it rejects a message. The key is confirmed by checking it encrypts the first revealed
+/// plain text block back to the first cipher text block
+///
+/// Errors
+/// If the cipher text is shorter than three blocks
+/// If the oracle does not reveal the plain text of C1 || 0 || C1, or the key is not the iv
+pub fn recover_key<D>(cipher_text: &[u8], decrypt: D) -> Result<Vec<u8>, KeyIsIvError>
+where
+    D: Fn(&[u8]) -> Result<(), Vec<u8>>,
+{
+    if cipher_text.len() < 3 * BLOCK_SIZE {
+        return Err(KeyIsIvError::TooShort {
+            length: cipher_text.len(),
+        });
+    }
+
+    let first_block = &cipher_text[..BLOCK_SIZE];
+    let modified = [first_block, &[0; BLOCK_SIZE], first_block].concat();
+    let plain_text = match decrypt(&modified) {
+        Ok(()) => return Err(KeyIsIvError::NoPlainTextRevealed),
+        Err(plain_text) => plain_text,
+    };
+    if plain_text.len() != 3 * BLOCK_SIZE {
+        return Err(KeyIsIvError::UnexpectedPlainText {
+            length: plain_text.len(),
+        });
+    }
+
+    let key: Vec<u8> = xor(&plain_text[..BLOCK_SIZE], &plain_text[2 * BLOCK_SIZE..]);
+
+    // P1 ^ K is the block that encrypts to C1
+    let mut block = xor(&plain_text[..BLOCK_SIZE], &key);
+    let cipher = Aes::new(&key).expect("key is one block long");
+    BlockCipher::encrypt_block(&cipher, &mut block);
+    if block != first_block {
+        return Err(KeyIsIvError::KeyNotConfirmed);
+    }
+
+    Ok(key)
+}
+
+fn xor(first: &[u8], second: &[u8]) -> Vec<u8> {
+    first.iter().zip(second).map(|(x, y)| x ^ y).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::cbc;
+    use crate::cyphers::aes::oracles::cbc_oracle::{CBCOracle, EncryptedMessage};
+    use crate::cyphers::aes::oracles::key_is_iv::{recover_key, KeyIsIvError};
+
+    #[test]
+    fn recovers_key_used_as_iv() {
+        let oracle = CBCOracle::new();
+        let encrypted = oracle.encrypt_key_is_iv(&"One block length".repeat(3).as_bytes());
+
+        let key = recover_key(&encrypted.cipher_text, |cipher_text| {
+            oracle.decrypt_and_validate(&EncryptedMessage {
+                cipher_text: cipher_text.to_vec(),
+                iv: encrypted.iv,
+            })
+        })
+        .unwrap();
+        assert_eq!(oracle.key.to_vec(), key);
+    }
+
+    #[test]
+    fn separate_iv_is_not_vulnerable() {
+        let oracle = CBCOracle::new();
+        let encrypted = oracle.encrypt("One block length".repeat(3).as_bytes());
+
+        // decrypting with the real iv instead of the key reveals plain text that doesn't confirm
+        let result = recover_key(&encrypted.cipher_text, |cipher_text| {
+            Err(cbc::decrypt(cipher_text, &oracle.key, &[7; 16]))
+        });
+        assert_eq!(Err(KeyIsIvError::KeyNotConfirmed), result);
+
+        let result = recover_key(&encrypted.cipher_text, |_| Ok(()));
+        assert_eq!(Err(KeyIsIvError::NoPlainTextRevealed), result);
+        assert!(matches!(
+            recover_key(&[0; 32], |_| Ok(())),
+            Err(KeyIsIvError::TooShort { length: 32 })
+        ));
+    }
+}
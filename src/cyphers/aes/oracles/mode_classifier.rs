@@ -0,0 +1,523 @@
+//! Guesses which mode produced a cipher text, as a probability for each candidate
+//!
+//! The classifier weighs up ECB, CBC and CTR (or any other stream mode) at each candidate block
+//! size against "unknown", data that doesn't look like the output of a cipher at all, using:
+//! - the length: block modes only produce whole blocks, so a block aligned length is `block_size`
+//!   times likelier under a block mode than under a stream mode
+//! - repeated blocks: ECB repeats a block whenever the plain text does, any other mode only by
+//!   chance (the birthday bound over the blocks of the cipher text)
+//! - the byte distribution: cipher text is uniformly distributed, anything else is modelled as a
+//!   symmetric Dirichlet-multinomial so that skewed data such as plain text favours "unknown"
+//!
+//! A cipher text on its own is often ambiguous, an ECB cipher text without a repeated block is
+//! indistinguishable from CBC, and random data is indistinguishable from CTR; querying a
+//! chosen-plaintext oracle with repeated bytes removes the ambiguity between the modes
+use std::collections::HashSet;
+use std::f64::consts::LN_2;
+
+use crate::cyphers::aes::AesMode;
+
+/// The concentration of the Dirichlet prior used to model non-uniform data
+const DIRICHLET_ALPHA: f64 = 0.1;
+/// How likely a chosen plain text of repeated bytes is to produce repeated ECB blocks
+const ORACLE_REPEAT_PROBABILITY: f64 = 1.0 - 1e-6;
+
+/// The probability of each mode having produced a cipher text, which sum to one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub ecb: f64,
+    pub cbc: f64,
+    /// CTR or any other mode that produces a stream of bytes rather than blocks
+    pub ctr: f64,
+    /// Data that doesn't look like the output of any cipher
+    pub unknown: f64,
+    /// The likeliest block size if a block mode was used
+    pub block_size: Option<usize>,
+}
+
+impl Classification {
+    /// The probability of the mode, zero for modes that are not considered
+    pub fn probability(&self, mode: &AesMode) -> f64 {
+        match mode {
+            AesMode::ECB => self.ecb,
+            AesMode::CBC => self.cbc,
+            AesMode::CTR => self.ctr,
+            _ => 0.0,
+        }
+    }
+
+    /// The likeliest mode, none if the data most likely isn't a cipher text
+    pub fn most_likely(&self) -> Option<AesMode> {
+        let (mode, probability) = self.most_likely_mode();
+        if self.unknown > probability {
+            None
+        } else {
+            Some(mode)
+        }
+    }
+
+    /// The likeliest mode if its probability is at least the threshold
+    pub fn confident(&self, threshold: f64) -> Option<AesMode> {
+        let (mode, probability) = self.most_likely_mode();
+        if probability >= threshold {
+            Some(mode)
+        } else {
+            None
+        }
+    }
+
+    /// The likeliest mode and its probability, even if the data most likely isn't a cipher text
+    pub fn most_likely_mode(&self) -> (AesMode, f64) {
+        [
+            (AesMode::ECB, self.ecb),
+            (AesMode::CBC, self.cbc),
+            (AesMode::CTR, self.ctr),
+        ]
+        .into_iter()
+        .fold((AesMode::CBC, f64::NEG_INFINITY), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hypothesis {
+    Ecb(usize),
+    Cbc(usize),
+    Stream,
+    Unknown,
+}
+
+/// What a cipher text shows about the mode that produced it
+struct Evidence {
+    length: usize,
+    /// For each candidate block size the number of whole blocks, and whether any repeat
+    blocks: Vec<(usize, usize, bool)>,
+    /// The log likelihood of the bytes, once repeated blocks are removed, as uniform and as
+    /// Dirichlet-multinomial data
+    uniform: f64,
+    skewed: f64,
+}
+
+impl Evidence {
+    fn repeats_at(&self, block_size: usize) -> bool {
+        self.blocks
+            .iter()
+            .any(|&(size, _, repeats)| size == block_size && repeats)
+    }
+
+    fn any_repeats(&self) -> bool {
+        self.blocks.iter().any(|&(_, _, repeats)| repeats)
+    }
+}
+
+pub struct ModeClassifier {
+    block_sizes: Vec<usize>,
+    repeat_probability: f64,
+}
+
+impl Default for ModeClassifier {
+    fn default() -> Self {
+        ModeClassifier {
+            block_sizes: vec![8, 16],
+            repeat_probability: 0.5,
+        }
+    }
+}
+
+impl ModeClassifier {
+    /// Considers 8 and 16 byte blocks, and assumes an even chance of the plain text repeating a
+    /// block
+    pub fn new() -> ModeClassifier {
+        ModeClassifier::default()
+    }
+
+    /// Panics
+    /// If there are no block sizes or any of them is zero
+    pub fn with_block_sizes(mut self, block_sizes: &[usize]) -> ModeClassifier {
+        assert!(
+            !block_sizes.is_empty() && !block_sizes.contains(&0),
+            "block sizes must be non-zero"
+        );
+        self.block_sizes = block_sizes.to_vec();
+        self
+    }
+
+    /// The chance of an unseen plain text repeating a whole block, which decides how strongly a
+    /// cipher text without repeated blocks counts against ECB
+    ///
+    /// Panics
+    /// If the probability is not between 0 and 1
+    pub fn with_repeat_probability(mut self, repeat_probability: f64) -> ModeClassifier {
+        assert!(
+            (0.0..=1.0).contains(&repeat_probability),
+            "repeat probability must be between 0 and 1"
+        );
+        self.repeat_probability = repeat_probability;
+        self
+    }
+
+    /// Classifies a cipher text without knowing anything about its plain text
+    pub fn classify(&self, cipher_text: &[u8]) -> Classification {
+        let evidence = self.evidence(cipher_text);
+        self.posterior(|hypothesis| {
+            self.log_likelihood(hypothesis, &evidence, self.repeat_probability)
+        })
+    }
+
+    /// Classifies an oracle that encrypts chosen plain texts, which may add a prefix or suffix
+    ///
+    /// The oracle is queried twice with runs of repeated bytes long enough to fill two aligned
+    /// blocks whatever the prefix, so ECB is all but certain to repeat a block; the oracle must
+    /// use the same mode for both queries
+    pub fn classify_oracle<F: Fn(&[u8]) -> Vec<u8>>(&self, oracle: F) -> Classification {
+        let max_block_size = *self
+            .block_sizes
+            .iter()
+            .max()
+            .expect("block sizes are non-empty");
+        let evidence: Vec<Evidence> = [3 * max_block_size, 3 * max_block_size + 1]
+            .into_iter()
+            .map(|length| self.evidence(&oracle(&vec![b'A'; length])))
+            .collect();
+
+        self.posterior(|hypothesis| {
+            evidence
+                .iter()
+                .map(|e| self.log_likelihood(hypothesis, e, ORACLE_REPEAT_PROBABILITY))
+                .sum()
+        })
+    }
+
+    fn evidence(&self, cipher_text: &[u8]) -> Evidence {
+        let blocks = self
+            .block_sizes
+            .iter()
+            .map(|&block_size| {
+                let whole_blocks: Vec<&[u8]> = cipher_text.chunks_exact(block_size).collect();
+                let unique = whole_blocks.iter().collect::<HashSet<_>>().len();
+                (block_size, whole_blocks.len(), unique < whole_blocks.len())
+            })
+            .collect();
+
+        // repeated blocks are explained by the repeat test, so only count each block once
+        let smallest = *self
+            .block_sizes
+            .iter()
+            .min()
+            .expect("block sizes are non-empty");
+        let mut seen = HashSet::new();
+        let unique_bytes: Vec<u8> = cipher_text
+            .chunks(smallest)
+            .filter(|block| seen.insert(*block))
+            .flatten()
+            .copied()
+            .collect();
+
+        let mut counts = [0usize; 256];
+        let mut skewed = 0.0;
+        for (i, &byte) in unique_bytes.iter().enumerate() {
+            skewed += ((counts[byte as usize] as f64 + DIRICHLET_ALPHA)
+                / (i as f64 + 256.0 * DIRICHLET_ALPHA))
+                .ln();
+            counts[byte as usize] += 1;
+        }
+
+        Evidence {
+            length: cipher_text.len(),
+            blocks,
+            uniform: -(unique_bytes.len() as f64) * 8.0 * LN_2,
+            skewed,
+        }
+    }
+
+    fn log_likelihood(
+        &self,
+        hypothesis: Hypothesis,
+        evidence: &Evidence,
+        repeat_probability: f64,
+    ) -> f64 {
+        let length = match hypothesis {
+            Hypothesis::Ecb(block_size) | Hypothesis::Cbc(block_size) => {
+                if evidence.length > 0 && evidence.length.is_multiple_of(block_size) {
+                    (block_size as f64).ln()
+                } else {
+                    f64::NEG_INFINITY
+                }
+            }
+            Hypothesis::Stream | Hypothesis::Unknown => 0.0,
+        };
+
+        let repeats: f64 = match hypothesis {
+            Hypothesis::Unknown => log_chance(repeat_probability, evidence.any_repeats()),
+            _ => evidence
+                .blocks
+                .iter()
+                .map(|&(size, blocks, repeats)| match hypothesis {
+                    Hypothesis::Ecb(block_size) if size == block_size => {
+                        log_chance(repeat_probability, repeats)
+                    }
+                    // repeated blocks imply repeats at any factor of the block size, and
+                    // repeats at a multiple of it are repeated blocks
+                    Hypothesis::Ecb(block_size)
+                        if (block_size.is_multiple_of(size) && evidence.repeats_at(block_size))
+                            || (size.is_multiple_of(block_size) && repeats) =>
+                    {
+                        0.0
+                    }
+                    _ if repeats => log_collision_probability(size, blocks),
+                    _ => 0.0,
+                })
+                .sum(),
+        };
+
+        let bytes = match hypothesis {
+            Hypothesis::Unknown => evidence.skewed,
+            _ => evidence.uniform,
+        };
+
+        length + repeats + bytes
+    }
+
+    /// Weighs each hypothesis by its likelihood, with each of the four modes equally likely
+    /// beforehand and the block modes splitting that evenly between the block sizes
+    fn posterior<L: Fn(Hypothesis) -> f64>(&self, log_likelihood: L) -> Classification {
+        let block_prior = (0.25 / self.block_sizes.len() as f64).ln();
+        let mut hypotheses = vec![
+            (Hypothesis::Stream, 0.25f64.ln()),
+            (Hypothesis::Unknown, 0.25f64.ln()),
+        ];
+        for &block_size in &self.block_sizes {
+            hypotheses.push((Hypothesis::Ecb(block_size), block_prior));
+            hypotheses.push((Hypothesis::Cbc(block_size), block_prior));
+        }
+
+        let log_posteriors: Vec<(Hypothesis, f64)> = hypotheses
+            .into_iter()
+            .map(|(hypothesis, prior)| (hypothesis, prior + log_likelihood(hypothesis)))
+            .collect();
+        let max = log_posteriors
+            .iter()
+            .map(|(_, p)| *p)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let posteriors: Vec<(Hypothesis, f64)> = log_posteriors
+            .into_iter()
+            .map(|(hypothesis, p)| (hypothesis, (p - max).exp()))
+            .collect();
+        let total: f64 = posteriors.iter().map(|(_, p)| p).sum();
+
+        let mut classification = Classification {
+            ecb: 0.0,
+            cbc: 0.0,
+            ctr: 0.0,
+            unknown: 0.0,
+            block_size: None,
+        };
+        let mut block_sizes = vec![0.0; self.block_sizes.len()];
+        for (hypothesis, p) in posteriors {
+            let p = p / total;
+            match hypothesis {
+                Hypothesis::Ecb(block_size) | Hypothesis::Cbc(block_size) => {
+                    if matches!(hypothesis, Hypothesis::Ecb(_)) {
+                        classification.ecb += p;
+                    } else {
+                        classification.cbc += p;
+                    }
+                    let index = self
+                        .block_sizes
+                        .iter()
+                        .position(|&b| b == block_size)
+                        .expect("hypotheses are built from the block sizes");
+                    block_sizes[index] += p;
+                }
+                Hypothesis::Stream => classification.ctr += p,
+                Hypothesis::Unknown => classification.unknown += p,
+            }
+        }
+
+        classification.block_size = self
+            .block_sizes
+            .iter()
+            .zip(block_sizes)
+            .filter(|(_, p)| *p > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(&block_size, _)| block_size);
+
+        classification
+    }
+}
+
+/// The log of the chance of an event with the given probability happening, or not
+fn log_chance(probability: f64, happened: bool) -> f64 {
+    if happened {
+        probability.ln()
+    } else {
+        (1.0 - probability).ln()
+    }
+}
+
+/// The log of the chance that some pair among the blocks is equal by coincidence
+fn log_collision_probability(block_size: usize, blocks: usize) -> f64 {
+    let pairs = (blocks * blocks.saturating_sub(1) / 2) as f64;
+    (pairs.ln() - block_size as f64 * 8.0 * LN_2).min(0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::cyphers::aes::oracles::mode_classifier::{Classification, ModeClassifier};
+    use crate::cyphers::aes::{cbc, ctr, ecb, generate_16_bit_key, get_random_bytes, AesMode};
+
+    const SAMPLES: usize = 3000;
+    const ENGLISH: &[u8] = b"I'm back and I'm ringin' the bell, a rockin' on the mike while the fly girls yell, in ecstasy in the back of me";
+
+    fn encrypt(mode: &AesMode, plain_text: &[u8]) -> Vec<u8> {
+        let key = generate_16_bit_key();
+        match mode {
+            AesMode::ECB => ecb::encrypt(&key, plain_text),
+            AesMode::CBC => cbc::encrypt(plain_text, &key, &generate_16_bit_key()),
+            AesMode::CTR => ctr::encrypt(&plain_text, &key, rand::random()).cipher_text,
+            _ => unreachable!(),
+        }
+    }
+
+    fn random_mode() -> AesMode {
+        [AesMode::ECB, AesMode::CBC, AesMode::CTR]
+            .into_iter()
+            .nth(rand::thread_rng().gen_range(0..3))
+            .unwrap()
+    }
+
+    /// 48 repeated bytes surrounded by 5 to 10 random bytes, as in challenge 11
+    fn repetitive_plain_text() -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        let prefix = get_random_bytes(rng.gen_range(5..11));
+        let suffix = get_random_bytes(rng.gen_range(5..11));
+        [&prefix[..], &[b'X'; 48], &suffix].concat()
+    }
+
+    fn assert_sums_to_one(classification: &Classification) {
+        let total =
+            classification.ecb + classification.cbc + classification.ctr + classification.unknown;
+        assert!((total - 1.0).abs() < 1e-9, "{classification:?}");
+    }
+
+    #[test]
+    fn classifies_repetitive_cipher_texts() {
+        let classifier = ModeClassifier::new();
+        let mut ctr_correct = 0;
+        for _ in 0..SAMPLES / 3 {
+            for mode in [AesMode::ECB, AesMode::CBC] {
+                let classification = classifier.classify(&encrypt(&mode, &repetitive_plain_text()));
+                assert_sums_to_one(&classification);
+                assert_eq!(
+                    classification.most_likely(),
+                    Some(mode),
+                    "{classification:?}"
+                );
+                assert_eq!(classification.block_size, Some(16));
+            }
+
+            let classification =
+                classifier.classify(&encrypt(&AesMode::CTR, &repetitive_plain_text()));
+            assert_sums_to_one(&classification);
+            if classification.most_likely() == Some(AesMode::CTR) {
+                ctr_correct += 1;
+            }
+        }
+
+        // a CTR cipher text that happens to be block aligned looks like CBC, these are 58 to 68
+        // bytes long so about one in six is
+        assert!(ctr_correct > SAMPLES / 3 * 3 / 4, "{ctr_correct}");
+    }
+
+    #[test]
+    fn classifies_oracles() {
+        let classifier = ModeClassifier::new();
+        for _ in 0..SAMPLES / 3 {
+            let mode = random_mode();
+            let key = generate_16_bit_key();
+            let prefix = get_random_bytes(rand::thread_rng().gen_range(0..40));
+            let oracle = |stimulus: &[u8]| {
+                let plain_text = [&prefix[..], stimulus, ENGLISH].concat();
+                match mode {
+                    AesMode::ECB => ecb::encrypt(&key, plain_text),
+                    AesMode::CBC => cbc::encrypt(&plain_text, &key, &[0; 16]),
+                    _ => ctr::encrypt(&plain_text, &key, 0).cipher_text,
+                }
+            };
+
+            let classification = classifier.classify_oracle(oracle);
+            assert_sums_to_one(&classification);
+            assert_eq!(
+                classification.confident(0.99),
+                Some(mode),
+                "{classification:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn confident_classifications_are_right() {
+        let classifier = ModeClassifier::new();
+        let mut confident = 0;
+        for _ in 0..SAMPLES {
+            let mode = random_mode();
+            let plain_text = if rand::random() {
+                repetitive_plain_text()
+            } else {
+                get_random_bytes(rand::thread_rng().gen_range(1..200))
+            };
+            let classification = classifier.classify(&encrypt(&mode, &plain_text));
+            if let Some(guess) = classification.confident(0.99) {
+                assert_eq!(guess, mode, "{classification:?}");
+                confident += 1;
+            }
+        }
+        assert!(confident > 0);
+    }
+
+    #[test]
+    fn short_ecb_is_not_mistaken_for_cbc() {
+        let classifier = ModeClassifier::new();
+        for _ in 0..SAMPLES {
+            let plain_text = get_random_bytes(rand::thread_rng().gen_range(1..16));
+            let classification = classifier.classify(&encrypt(&AesMode::ECB, &plain_text));
+            assert_eq!(classification.confident(0.9), None, "{classification:?}");
+            assert_eq!(classification.block_size, Some(16));
+        }
+    }
+
+    #[test]
+    fn random_data_is_never_ecb() {
+        let classifier = ModeClassifier::new();
+        for _ in 0..SAMPLES {
+            let data = get_random_bytes(rand::thread_rng().gen_range(1..512));
+            let classification = classifier.classify(&data);
+            assert!(classification.ecb < 0.5, "{classification:?}");
+            assert_ne!(classification.confident(0.5), Some(AesMode::ECB));
+            if !data.len().is_multiple_of(8) {
+                assert_eq!(classification.cbc, 0.0);
+                assert_eq!(classification.block_size, None);
+            }
+        }
+    }
+
+    #[test]
+    fn plain_text_is_unknown() {
+        let classifier = ModeClassifier::new();
+        for length in (64..ENGLISH.len()).step_by(8) {
+            let classification = classifier.classify(&ENGLISH[..length]);
+            assert_eq!(classification.most_likely(), None, "{classification:?}");
+            assert!(classification.unknown > 0.99);
+        }
+
+        let classification = classifier.classify(&[]);
+        assert_eq!(classification.ecb + classification.cbc, 0.0);
+    }
+}
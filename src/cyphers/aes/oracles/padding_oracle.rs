@@ -1,91 +1,277 @@
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use rand::Rng;
 
 use crate::cyphers::aes::oracles::cbc_oracle::EncryptedMessage;
 use crate::cyphers::aes::{self, cbc};
-use crate::cyphers::padding::pkcs7;
+use crate::cyphers::padding::pkcs7::{self, Pkcs7};
+use crate::cyphers::padding::Padding;
 use crate::encoding::base64::Base64;
 use crate::encoding::Digest;
+use crate::Error;
 
-fn calculate_padding_iv(block_length: usize, flipped_block: &mut [u8; 16], i: usize) -> [u8; 16] {
-    let target_byte = block_length - i;
-    let padding_iv: Vec<u8> = flipped_block
-        .iter_mut()
-        .zip(
-            vec![
-                vec![0; block_length - target_byte + 1],
-                vec![target_byte as u8; target_byte - 1],
-            ]
-            .into_iter()
-            .flatten()
-            .into_iter(),
-        )
-        .map(|(x, y)| *x ^ y)
-        .collect();
-    padding_iv.try_into().unwrap()
-}
+pub mod http;
 
+/// Answers whether a cipher text decrypts, under the given iv, to correctly padded plain text
 pub trait PaddingOracle {
-    fn is_valid_padding(&self, message: &EncryptedMessage) -> bool;
-
-    fn decrypt(&self, encrypted: &EncryptedMessage) -> Vec<u8> {
-        let cipher_text = encrypted.cipher_text.clone();
-        let mut last_block = encrypted.iv.to_vec();
-        let block_length = last_block.len();
-
-        let mut decrypted = Vec::new();
-        cipher_text.chunks(block_length).for_each(|block| {
-            let mut zero_iv = [0; 16];
-            for i in (0..16).rev() {
-                let mut padding_iv = calculate_padding_iv(block_length, &mut zero_iv, i);
-                *zero_iv.get_mut(i).unwrap() =
-                    self.find_dec_key_value(block, 16, &mut padding_iv, i);
-            }
-            let mut decrypted_block: Vec<u8> = zero_iv
-                .iter()
-                .zip(last_block.iter())
-                .map(|(x, y)| x ^ y)
-                .collect();
-            decrypted.append(&mut decrypted_block);
-            last_block = block.to_vec()
-        });
+    /// Errors
+    /// If the oracle could not be queried, e.g. a remote oracle was unreachable
+    fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error>;
+
+    /// Recovers the plain text of an AES CBC message with pkcs#7 padding, which is removed
+    ///
+    /// Errors
+    /// If the oracle answers inconsistently or the recovered plain text is not correctly padded
+    fn decrypt(&self, encrypted: &EncryptedMessage) -> Result<Vec<u8>, Error> {
+        let decryption = PaddingOracleAttack::new(encrypted.iv.len()).decrypt(
+            self,
+            &encrypted.cipher_text,
+            &encrypted.iv,
+        )?;
+        Ok(decryption.plain_text)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PaddingOracleError {
+    InvalidCipherTextLength { length: usize, block_size: usize },
+    InvalidIvLength { length: usize, block_size: usize },
+    NoValidByte { block: usize, position: usize },
+}
+
+impl Display for PaddingOracleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaddingOracleError::InvalidCipherTextLength { length, block_size } => write!(
+                f,
+                "cipher text length {length} is not a non-zero multiple of the {block_size} byte block size"
+            ),
+            PaddingOracleError::InvalidIvLength { length, block_size } => write!(
+                f,
+                "iv length {length} does not match the {block_size} byte block size"
+            ),
+            PaddingOracleError::NoValidByte { block, position } => write!(
+                f,
+                "oracle accepted none of the 256 values for byte {position} of block {block}; it is \
+                 answering inconsistently or not checking the expected padding"
+            ),
+        }
+    }
+}
+
+impl StdError for PaddingOracleError {}
+
+/// The result of a padding oracle attack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaddingOracleDecryption {
+    /// The recovered plain text with the padding removed
+    pub plain_text: Vec<u8>,
+    /// The number of oracle queries spent recovering each byte of the padded plain text
+    pub queries: Vec<usize>,
+}
+
+impl PaddingOracleDecryption {
+    pub fn total_queries(&self) -> usize {
+        self.queries.iter().sum()
+    }
+}
+
+/// A cipher text forged with a padding oracle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaddingOracleEncryption {
+    pub cipher_text: Vec<u8>,
+    pub iv: Vec<u8>,
+    /// The number of oracle queries spent forging each byte of the padded plain text
+    pub queries: Vec<usize>,
+}
+
+impl PaddingOracleEncryption {
+    pub fn total_queries(&self) -> usize {
+        self.queries.iter().sum()
+    }
+}
 
-        pkcs7::try_unpad(&decrypted, block_length).unwrap()
+/// Decrypts CBC cipher texts one byte at a time using an oracle that reveals whether the padding
+/// is valid
+///
+/// For each byte the forged iv is chosen so that the bytes after it decrypt to the padding the
+/// scheme expects, then every value is tried until the oracle accepts one. Works for any
+/// deterministic padding that is checked in full, such as pkcs#7, ANSI X.923 and ISO/IEC 7816-4
+///
+/// Run in reverse (CBC-R) the same block decryptions forge a cipher text for any chosen plain
+/// text, see [PaddingOracleAttack::encrypt]
+pub struct PaddingOracleAttack<P: Padding = Pkcs7> {
+    block_size: usize,
+    padding: P,
+}
+
+impl PaddingOracleAttack {
+    pub fn new(block_size: usize) -> PaddingOracleAttack {
+        PaddingOracleAttack::with_padding(block_size, Pkcs7)
+    }
+}
+
+impl<P: Padding> PaddingOracleAttack<P> {
+    pub fn with_padding(block_size: usize, padding: P) -> PaddingOracleAttack<P> {
+        PaddingOracleAttack {
+            block_size,
+            padding,
+        }
     }
 
-    fn find_dec_key_value(
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Recovers the plain text of the cipher text and removes its padding
+    ///
+    /// Errors
+    /// If the cipher text is not a whole number of blocks or the iv is not one block long
+    /// If the oracle rejects every value for a byte, which means it is inconsistent or is not
+    /// checking this padding scheme
+    /// If the recovered plain text is not correctly padded
+    pub fn decrypt<O: PaddingOracle + ?Sized>(
         &self,
+        oracle: &O,
         cipher_text: &[u8],
-        block_length: usize,
-        iv: &mut [u8; 16],
+        iv: &[u8],
+    ) -> Result<PaddingOracleDecryption, Error> {
+        let block_size = self.block_size;
+        if cipher_text.is_empty() || !cipher_text.len().is_multiple_of(block_size) {
+            return Err(Box::new(PaddingOracleError::InvalidCipherTextLength {
+                length: cipher_text.len(),
+                block_size,
+            }));
+        }
+        if iv.len() != block_size {
+            return Err(Box::new(PaddingOracleError::InvalidIvLength {
+                length: iv.len(),
+                block_size,
+            }));
+        }
+
+        let mut padded = Vec::with_capacity(cipher_text.len());
+        let mut queries = Vec::with_capacity(cipher_text.len());
+        let mut last_block = iv;
+        for (index, block) in cipher_text.chunks(block_size).enumerate() {
+            let (intermediate, mut block_queries) = self.decrypt_block(oracle, block, index)?;
+            padded.extend(intermediate.iter().zip(last_block).map(|(x, y)| x ^ y));
+            queries.append(&mut block_queries);
+            last_block = block;
+        }
+
+        Ok(PaddingOracleDecryption {
+            plain_text: self.padding.unpad(&padded, block_size)?,
+            queries,
+        })
+    }
+
+    /// Forges an iv and cipher text that decrypt to the padded plain text without knowing the key
+    ///
+    /// Starting from a random final block, each block's decryption is recovered with the oracle
+    /// and xor'd with the plain text wanted from it to give the previous cipher text block; the
+    /// block before the first becomes the iv. Costs as many queries as decrypting the same length
+    ///
+    /// Errors
+    /// If the oracle rejects every value for a byte, which means it is inconsistent or is not
+    /// checking this padding scheme
+    pub fn encrypt<O: PaddingOracle + ?Sized>(
+        &self,
+        oracle: &O,
+        plain_text: &[u8],
+    ) -> Result<PaddingOracleEncryption, Error> {
+        let block_size = self.block_size;
+        let padded = self.padding.pad(plain_text, block_size);
+
+        let mut blocks = vec![aes::get_random_bytes(block_size)];
+        let mut queries = vec![0; padded.len()];
+        for (index, plain_block) in padded.chunks(block_size).enumerate().rev() {
+            let block = blocks.last().expect("starts with a random block");
+            let (intermediate, block_queries) = self.decrypt_block(oracle, block, index)?;
+            queries[index * block_size..(index + 1) * block_size].copy_from_slice(&block_queries);
+            blocks.push(
+                intermediate
+                    .iter()
+                    .zip(plain_block)
+                    .map(|(x, y)| x ^ y)
+                    .collect(),
+            );
+        }
+
+        blocks.reverse();
+        let iv = blocks.remove(0);
+        Ok(PaddingOracleEncryption {
+            cipher_text: blocks.concat(),
+            iv,
+            queries,
+        })
+    }
+
+    /// Recovers the block cipher decryption of a single block, before it is xor'd with the
+    /// previous block, along with the queries spent on each byte
+    ///
+    /// Errors
+    /// If the oracle fails or accepts none of the values for a byte
+    fn decrypt_block<O: PaddingOracle + ?Sized>(
+        &self,
+        oracle: &O,
+        block: &[u8],
         index: usize,
-    ) -> u8 {
-        for i in 0..=255 {
-            *iv.get_mut(index).unwrap() = i;
-            let trial_message = EncryptedMessage {
-                cipher_text: cipher_text.to_vec(),
-                iv: *iv,
-            };
-            if self.is_valid_padding(&trial_message) {
-                if index == 0 {
-                    return i ^ (block_length - index) as u8;
-                } else {
-                    *iv.get_mut(index - 1).unwrap() = 1;
-                    let trial_message = EncryptedMessage {
-                        cipher_text: cipher_text.to_vec(),
-                        iv: *iv,
-                    };
-                    if self.is_valid_padding(&trial_message) {
-                        return i ^ (block_length - index) as u8;
-                    }
-                    *iv.get_mut(index - 1).unwrap() = 0;
+    ) -> Result<(Vec<u8>, Vec<usize>), Error> {
+        let block_size = self.block_size;
+        let mut intermediate = vec![0; block_size];
+        let mut queries = vec![0; block_size];
+
+        for position in (0..block_size).rev() {
+            let target = self.valid_ending(position);
+            let mut forged_iv = vec![0; block_size];
+            for (i, byte) in forged_iv.iter_mut().enumerate().skip(position + 1) {
+                *byte = intermediate[i] ^ target[i - position];
+            }
+
+            let mut found = None;
+            for guess in 0..=255 {
+                forged_iv[position] = guess;
+                queries[position] += 1;
+                if !oracle.is_valid_padding(block, &forged_iv)? {
+                    continue;
+                }
+                if position == 0 {
+                    found = Some(guess);
+                    break;
+                }
+
+                // the padding may have been valid by chance because of the byte before the one
+                // being guessed, so check it still is when that byte changes
+                forged_iv[position - 1] ^= 1;
+                queries[position] += 1;
+                let confirmed = oracle.is_valid_padding(block, &forged_iv)?;
+                forged_iv[position - 1] ^= 1;
+                if confirmed {
+                    found = Some(guess);
+                    break;
+                }
+            }
+
+            match found {
+                Some(guess) => intermediate[position] = guess ^ target[0],
+                None => {
+                    return Err(Box::new(PaddingOracleError::NoValidByte {
+                        block: index,
+                        position,
+                    }))
                 }
             }
         }
 
-        // we'll only get here if the target oracle is not using cbc/validating padding
-        panic!("invalid padding oracle");
+        Ok((intermediate, queries))
+    }
+
+    /// The bytes from the position to the end of a block that make the padding valid
+    fn valid_ending(&self, position: usize) -> Vec<u8> {
+        self.padding.pad(&vec![0; position], self.block_size)[position..].to_vec()
     }
 }
 
@@ -94,10 +280,14 @@ pub struct SamplePaddingOracle {
 }
 
 impl PaddingOracle for SamplePaddingOracle {
-    fn is_valid_padding(&self, message: &EncryptedMessage) -> bool {
-        let message = cbc::decrypt(&message.cipher_text, &self.key, &message.iv);
-        let unpadded = pkcs7::try_unpad(&message, self.key.len());
-        unpadded.is_ok()
+    fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error> {
+        if iv.len() != self.key.len() {
+            return Ok(false);
+        }
+        Ok(match cbc::try_decrypt(cipher_text, &self.key, iv) {
+            Ok(message) => pkcs7::try_unpad(&message, self.key.len()).is_ok(),
+            Err(_) => false,
+        })
     }
 }
 
@@ -139,3 +329,102 @@ impl SamplePaddingOracle {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::oracles::padding_oracle::{
+        PaddingOracle, PaddingOracleAttack, SamplePaddingOracle,
+    };
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::cbc::Cbc;
+    use crate::cyphers::padding::ansi_x923::AnsiX923;
+    use crate::cyphers::padding::iso7816::Iso7816;
+    use crate::cyphers::padding::Padding;
+    use crate::Error;
+
+    struct FeistelOracle<P: Padding> {
+        cbc: Cbc<Feistel, P>,
+    }
+
+    impl<P: Padding> PaddingOracle for FeistelOracle<P> {
+        fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error> {
+            Ok(self.cbc.try_decrypt(cipher_text, iv).is_ok())
+        }
+    }
+
+    struct RejectingOracle;
+
+    impl PaddingOracle for RejectingOracle {
+        fn is_valid_padding(&self, _: &[u8], _: &[u8]) -> Result<bool, Error> {
+            Ok(false)
+        }
+    }
+
+    const PLAIN_TEXT: &[u8] = b"attack at dawn, bring the \x80 marker";
+
+    #[test]
+    fn decrypts_sample_oracle() {
+        let oracle = SamplePaddingOracle::new();
+        let encrypted = oracle.encrypt_rand();
+
+        let decrypted = oracle.decrypt(&encrypted).unwrap();
+        assert!(decrypted.starts_with(b"00000"));
+    }
+
+    #[test]
+    fn decrypts_eight_byte_blocks_with_iso7816() {
+        let cbc = Cbc::with_padding(Feistel::new(b"key"), Iso7816);
+        let iv = [7; 8];
+        let cipher_text = cbc.encrypt(PLAIN_TEXT, &iv);
+        let oracle = FeistelOracle { cbc };
+
+        let decryption = PaddingOracleAttack::with_padding(8, Iso7816)
+            .decrypt(&oracle, &cipher_text, &iv)
+            .unwrap();
+        assert_eq!(PLAIN_TEXT, decryption.plain_text);
+        assert_eq!(cipher_text.len(), decryption.queries.len());
+        assert!(decryption.queries.iter().all(|count| *count > 0));
+    }
+
+    #[test]
+    fn decrypts_ansi_x923() {
+        let cbc = Cbc::with_padding(Feistel::new(b"another key"), AnsiX923);
+        let iv = [1, 2, 3, 4, 5, 6, 7, 8];
+        let cipher_text = cbc.encrypt(PLAIN_TEXT, &iv);
+        let oracle = FeistelOracle { cbc };
+
+        let decryption = PaddingOracleAttack::with_padding(8, AnsiX923)
+            .decrypt(&oracle, &cipher_text, &iv)
+            .unwrap();
+        assert_eq!(PLAIN_TEXT, decryption.plain_text);
+    }
+
+    #[test]
+    fn forges_chosen_plain_text() {
+        let cbc = Cbc::with_padding(Feistel::new(b"unknown key"), Iso7816);
+        let oracle = FeistelOracle { cbc };
+
+        let forged = PaddingOracleAttack::with_padding(8, Iso7816)
+            .encrypt(&oracle, PLAIN_TEXT)
+            .unwrap();
+        assert_eq!(
+            PLAIN_TEXT,
+            oracle
+                .cbc
+                .try_decrypt(&forged.cipher_text, &forged.iv)
+                .unwrap()
+        );
+        assert_eq!(forged.cipher_text.len(), forged.queries.len());
+    }
+
+    #[test]
+    fn inconsistent_oracle_is_reported() {
+        let result = PaddingOracleAttack::new(16).decrypt(&RejectingOracle, &[0; 32], &[0; 16]);
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("byte 15 of block 0"), "{message}");
+
+        assert!(PaddingOracleAttack::new(16)
+            .decrypt(&RejectingOracle, &[0; 20], &[0; 16])
+            .is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! A padding oracle reached over HTTP, such as the `/padding-oracle` route of `simple-server`
+//!
+//! Tokens are sent as the hex encoded iv followed by the cipher text in the `token` query
+//! parameter. The server answers 200 when the padding is valid and 403 when it is not; network
+//! errors, 429 and 5xx responses are retried and any other status is an error.
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::cyphers::aes::oracles::http::{self, RetryingClient};
+use crate::cyphers::aes::oracles::padding_oracle::PaddingOracle;
+use crate::encoding::hex::Hex;
+use crate::Error;
+
+pub struct HttpPaddingOracle {
+    client: RetryingClient,
+    url: String,
+}
+
+impl HttpPaddingOracle {
+    /// Queries the endpoint at the url, e.g. `http://127.0.0.1:8080/padding-oracle`
+    ///
+    /// By default failed requests are retried three times and requests are not rate limited
+    pub fn new(url: &str) -> HttpPaddingOracle {
+        HttpPaddingOracle {
+            client: RetryingClient::new(),
+            url: url.to_string(),
+        }
+    }
+
+    /// Sets how many times a request is retried after a network error, 429 or 5xx response, and
+    /// the delay before the first retry, which doubles for each one after
+    pub fn with_retries(mut self, retries: usize, retry_delay: Duration) -> HttpPaddingOracle {
+        self.client.set_retries(retries, retry_delay);
+        self
+    }
+
+    /// Limits the rate of requests by waiting at least the interval between the start of each
+    pub fn with_min_interval(mut self, min_interval: Duration) -> HttpPaddingOracle {
+        self.client.set_min_interval(min_interval);
+        self
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl PaddingOracle for HttpPaddingOracle {
+    /// Errors
+    /// If the server responds with a status other than 200 or 403, or cannot be reached after
+    /// every retry
+    fn is_valid_padding(&self, cipher_text: &[u8], iv: &[u8]) -> Result<bool, Error> {
+        let token = Hex::new(&[iv, cipher_text].concat()).to_string();
+        let response = self.client.get(&self.url, &[("token", &token)])?;
+
+        match response.status() {
+            StatusCode::OK => Ok(true),
+            StatusCode::FORBIDDEN => Ok(false),
+            status => Err(http::unexpected_status(status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::cyphers::aes::oracles::http::test::serve;
+    use crate::cyphers::aes::oracles::padding_oracle::http::HttpPaddingOracle;
+    use crate::cyphers::aes::oracles::padding_oracle::PaddingOracle;
+
+    #[test]
+    fn maps_statuses_and_retries() {
+        let address = serve(
+            "GET /padding-oracle?token=0102",
+            &[
+                ("200 OK", ""),
+                ("503 Service Unavailable", ""),
+                ("403 Forbidden", ""),
+                ("404 Not Found", ""),
+            ],
+        );
+        let oracle = HttpPaddingOracle::new(&format!("{address}/padding-oracle"))
+            .with_retries(1, Duration::from_millis(1));
+
+        assert!(oracle.is_valid_padding(&[2], &[1]).unwrap());
+        assert!(!oracle.is_valid_padding(&[2], &[1]).unwrap());
+        assert!(oracle.is_valid_padding(&[2], &[1]).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::cyphers::aes::cipher::Aes;
+use crate::cyphers::modes::pcbc::Pcbc;
+use crate::Error;
+
+/// Pads the plain text using pkcs#7 and encrypts it with AES in PCBC mode
+///
+/// Panics
+/// If the key is not 16, 24 or 32 bytes long, or the iv is not 16 bytes long
+pub fn encrypt(plain_text: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    Pcbc::new(Aes::new(key).expect("invalid aes key")).encrypt(plain_text, iv)
+}
+
+/// Decrypts an AES PCBC cipher text; padding is left in place
+///
+/// Errors
+/// If the key is not 16, 24 or 32 bytes long, or the cipher text is not a whole number of blocks
+///
+/// Panics
+/// If the iv is not 16 bytes long
+pub fn try_decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+    Pcbc::new(Aes::new(key)?).decrypt(encrypted_message, iv)
+}
+
+/// Decrypts an AES PCBC cipher text; padding is left in place
+///
+/// Panics
+/// If decryption fails for any reason, or the iv is not 16 bytes long
+pub fn decrypt(encrypted_message: &[u8], key: &[u8], iv: &[u8]) -> Vec<u8> {
+    try_decrypt(encrypted_message, key, iv).expect("decryption failed")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::aes::pcbc::{decrypt, encrypt, try_decrypt};
+    use crate::cyphers::padding::pkcs7;
+
+    #[test]
+    fn round_trip() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [7; 16];
+        let plain_text = "propagating cipher block chaining".as_bytes();
+
+        let encrypted = encrypt(plain_text, key, &iv);
+        assert_eq!(48, encrypted.len());
+        assert_eq!(
+            plain_text,
+            pkcs7::try_unpad(&decrypt(&encrypted, key, &iv), 16).unwrap()
+        );
+    }
+
+    #[test]
+    fn corrupted_block_garbles_the_rest() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [7; 16];
+        let plain_text = "A".repeat(64);
+
+        let mut encrypted = encrypt(plain_text.as_bytes(), key, &iv);
+        encrypted[20] ^= 1;
+        let decrypted = decrypt(&encrypted, key, &iv);
+
+        assert_eq!(plain_text.as_bytes()[..16], decrypted[..16]);
+        decrypted[16..64]
+            .chunks(16)
+            .for_each(|block| assert_ne!(plain_text.as_bytes()[..16], *block));
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [7; 16];
+        let encrypted = encrypt("some message".as_bytes(), key, &iv);
+
+        assert!(try_decrypt(&encrypted[..15], key, &iv).is_err());
+        assert!(try_decrypt(&encrypted, &key[..5], &iv).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+/// A keyed permutation over fixed size blocks of bytes
+///
+/// Modes of operation (see cyphers::modes) are written against this trait rather than against AES
+/// so that the same mode, oracle or attack can be run over any cipher regardless of its block size
+pub trait BlockCipher {
+    /// The size in bytes of the blocks this cipher operates on
+    fn block_size(&self) -> usize;
+
+    /// Encrypts a single block in place
+    ///
+    /// Panics
+    /// If the block is not exactly block_size bytes long
+    fn encrypt_block(&self, block: &mut [u8]);
+
+    /// Decrypts a single block in place
+    ///
+    /// Panics
+    /// If the block is not exactly block_size bytes long
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl<C: BlockCipher> BlockCipher for &C {
+    fn block_size(&self) -> usize {
+        (*self).block_size()
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        (*self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        (*self).decrypt_block(block)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::cyphers::block_cipher::BlockCipher;
+
+pub const BLOCK_SIZE: usize = 8;
+const ROUNDS: usize = 16;
+
+/// A toy 64 bit block cipher built from a balanced Feistel network
+///
+/// This is not secure and should never be used to protect anything. It exists so that the modes
+/// of operation, and the attacks on them, can be exercised against a cipher with an 8 byte block
+#[derive(Debug, Clone)]
+pub struct Feistel {
+    round_keys: [u32; ROUNDS],
+}
+
+impl Feistel {
+    /// Builds the round keys from a key of any length
+    pub fn new(key: &[u8]) -> Feistel {
+        // FNV-1a style folding of the key bytes into each round key
+        let mut state: u32 = 0x811c_9dc5;
+        let mut round_keys = [0; ROUNDS];
+        for (round, round_key) in round_keys.iter_mut().enumerate() {
+            state ^= round as u32;
+            for byte in key {
+                state = (state ^ *byte as u32).wrapping_mul(0x0100_0193);
+            }
+            *round_key = state;
+        }
+
+        Feistel { round_keys }
+    }
+}
+
+impl BlockCipher for Feistel {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let (mut left, mut right) = split_block(block);
+        for round_key in self.round_keys {
+            (left, right) = (right, left ^ round_function(right, round_key));
+        }
+        join_block(block, left, right);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let (mut left, mut right) = split_block(block);
+        for round_key in self.round_keys.into_iter().rev() {
+            (left, right) = (right ^ round_function(left, round_key), left);
+        }
+        join_block(block, left, right);
+    }
+}
+
+fn split_block(block: &[u8]) -> (u32, u32) {
+    assert_eq!(BLOCK_SIZE, block.len(), "feistel blocks are 8 bytes");
+    let (left, right) = block.split_at(BLOCK_SIZE / 2);
+    (
+        u32::from_be_bytes(left.try_into().unwrap()),
+        u32::from_be_bytes(right.try_into().unwrap()),
+    )
+}
+
+fn join_block(block: &mut [u8], left: u32, right: u32) {
+    block[..BLOCK_SIZE / 2].copy_from_slice(&left.to_be_bytes());
+    block[BLOCK_SIZE / 2..].copy_from_slice(&right.to_be_bytes());
+}
+
+// a Feistel round function doesn't need to be invertible, it just needs to mix well
+fn round_function(half: u32, round_key: u32) -> u32 {
+    let mixed = (half ^ round_key).wrapping_mul(0x2545_f491);
+    mixed ^ mixed.rotate_right(15) ^ (mixed >> 7)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::block_cipher::BlockCipher;
+    use crate::cyphers::feistel::Feistel;
+
+    #[test]
+    fn decryption_reverses_encryption() {
+        let cipher = Feistel::new("YELLOW SUBMARINE".as_bytes());
+        let plain_text = *b"8 bytes!";
+
+        let mut block = plain_text;
+        cipher.encrypt_block(&mut block);
+        assert_ne!(plain_text, block);
+
+        cipher.decrypt_block(&mut block);
+        assert_eq!(plain_text, block);
+    }
+
+    #[test]
+    fn different_keys_give_different_cipher_text() {
+        let mut block_one = *b"8 bytes!";
+        let mut block_two = block_one;
+
+        Feistel::new("YELLOW SUBMARINE".as_bytes()).encrypt_block(&mut block_one);
+        Feistel::new("YELLOW SUBMARINF".as_bytes()).encrypt_block(&mut block_two);
+
+        assert_ne!(block_one, block_two);
+    }
+}
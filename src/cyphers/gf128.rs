@@ -0,0 +1,177 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+
+use crate::encoding::hex::Hex;
+
+pub mod polynomial;
+
+// x^128 + x^7 + x^2 + x + 1 with the bits reflected as GCM orders them
+const REDUCTION: u128 = 0xe1 << 120;
+
+/// An element of GF(2^128) using the bit ordering of GCM (NIST SP 800-38D)
+///
+/// A 16 byte block maps to a polynomial with the most significant bit of the first byte as the
+/// coefficient of x^0 and the least significant bit of the last byte as the coefficient of x^127.
+/// Addition is xor; multiplication is carry-less multiplication modulo x^128 + x^7 + x^2 + x + 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Gf128(u128);
+
+impl Gf128 {
+    pub fn zero() -> Gf128 {
+        Gf128(0)
+    }
+
+    pub fn one() -> Gf128 {
+        Gf128(1 << 127)
+    }
+
+    /// The element represented by a block of up to 16 bytes; short blocks are padded with zeros
+    ///
+    /// Panics
+    /// If the block is longer than 16 bytes
+    pub fn from_block(block: &[u8]) -> Gf128 {
+        let mut bytes = [0; 16];
+        bytes[..block.len()].copy_from_slice(block);
+        Gf128(u128::from_be_bytes(bytes))
+    }
+
+    pub fn to_block(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Raises this element to the given power by square-and-multiply
+    pub fn pow(self, mut exponent: u128) -> Gf128 {
+        let mut result = Gf128::one();
+        let mut base = self;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result *= base;
+            }
+            base *= base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Returns the unique square root
+    ///
+    /// Squaring is a permutation of order 128 on this field, so the square root is self^(2^127)
+    pub fn sqrt(self) -> Gf128 {
+        self.pow(1 << 127)
+    }
+
+    /// Returns a uniformly random element
+    pub fn random() -> Gf128 {
+        Gf128(rand::random())
+    }
+
+    /// Returns the multiplicative inverse, or None for zero
+    ///
+    /// The multiplicative group has order 2^128 - 1 so the inverse is self^(2^128 - 2)
+    pub fn inverse(self) -> Option<Gf128> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self.pow(u128::MAX - 1))
+        }
+    }
+}
+
+impl Display for Gf128 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Hex::new(&self.to_block()))
+    }
+}
+
+// addition in a field of characteristic 2 is xor
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Add for Gf128 {
+    type Output = Gf128;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Gf128(self.0 ^ rhs.0)
+    }
+}
+
+#[allow(clippy::suspicious_op_assign_impl)]
+impl AddAssign for Gf128 {
+    fn add_assign(&mut self, rhs: Self) {
+        self.0 ^= rhs.0;
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+
+    /// Algorithm 1 of NIST SP 800-38D
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut product = 0;
+        let mut multiple = rhs.0;
+        for i in (0..128).rev() {
+            if self.0 >> i & 1 == 1 {
+                product ^= multiple;
+            }
+            multiple = if multiple & 1 == 1 {
+                (multiple >> 1) ^ REDUCTION
+            } else {
+                multiple >> 1
+            };
+        }
+        Gf128(product)
+    }
+}
+
+impl MulAssign for Gf128 {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::cyphers::gf128::Gf128;
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    fn element(hex: &str) -> Gf128 {
+        Gf128::from_block(Hex::from_str(hex).unwrap().bytes())
+    }
+
+    #[test]
+    fn one_is_multiplicative_identity() {
+        let a = element("66e94bd4ef8a2c3b884cfa59ca342b2e");
+
+        assert_eq!(a, a * Gf128::one());
+        assert_eq!(Gf128::zero(), a * Gf128::zero());
+    }
+
+    #[test]
+    fn multiplication_matches_known_product() {
+        // H and X1 from test case 2 of the GCM specification; X1 = C1 * H
+        let h = element("66e94bd4ef8a2c3b884cfa59ca342b2e");
+        let c = element("0388dace60b6a392f328c2b971b2fe78");
+
+        assert_eq!(element("5e2ec746917062882c85b0685353deb7"), c * h);
+    }
+
+    #[test]
+    fn inverse_multiplies_to_one() {
+        let a = element("0388dace60b6a392f328c2b971b2fe78");
+
+        assert_eq!(Gf128::one(), a * a.inverse().unwrap());
+        assert_eq!(None, Gf128::zero().inverse());
+    }
+
+    #[test]
+    fn sqrt_reverses_squaring() {
+        let a = element("0388dace60b6a392f328c2b971b2fe78");
+
+        assert_eq!(a, (a * a).sqrt());
+    }
+}
@@ -0,0 +1,410 @@
+use std::ops::{Add, Mul};
+
+use crate::cyphers::gf128::Gf128;
+
+/// The number of squarings that make up the Frobenius map x -> x^q for q = 2^128
+const FIELD_DEGREE: usize = 128;
+
+/// A polynomial with coefficients in GF(2^128)
+///
+/// Coefficients are stored lowest degree first with no trailing zeros, so the zero polynomial
+/// has no coefficients at all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    coefficients: Vec<Gf128>,
+}
+
+impl Polynomial {
+    /// Builds a polynomial from its coefficients, lowest degree first
+    pub fn new(coefficients: Vec<Gf128>) -> Polynomial {
+        let mut polynomial = Polynomial { coefficients };
+        polynomial.trim();
+        polynomial
+    }
+
+    pub fn zero() -> Polynomial {
+        Polynomial::new(Vec::new())
+    }
+
+    pub fn one() -> Polynomial {
+        Polynomial::new(vec![Gf128::one()])
+    }
+
+    /// The polynomial x
+    pub fn x() -> Polynomial {
+        Polynomial::new(vec![Gf128::zero(), Gf128::one()])
+    }
+
+    /// The degree of the polynomial; None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        *self == Polynomial::one()
+    }
+
+    pub fn coefficients(&self) -> &[Gf128] {
+        &self.coefficients
+    }
+
+    /// Evaluates the polynomial at the given point using Horner's method
+    pub fn evaluate(&self, point: Gf128) -> Gf128 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Gf128::zero(), |accum, coefficient| {
+                accum * point + *coefficient
+            })
+    }
+
+    /// Divides through by the leading coefficient
+    ///
+    /// The zero polynomial is returned unchanged
+    pub fn monic(&self) -> Polynomial {
+        match self.coefficients.last() {
+            Some(leading) => {
+                let inverse = leading
+                    .inverse()
+                    .expect("leading coefficient is never zero");
+                self.scale(inverse)
+            }
+            None => Polynomial::zero(),
+        }
+    }
+
+    /// Returns the quotient and remainder of polynomial long division
+    ///
+    /// Panics
+    /// If the divisor is the zero polynomial
+    pub fn div_rem(&self, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        let divisor_degree = divisor.degree().expect("division by the zero polynomial");
+        let leading_inverse = divisor.coefficients[divisor_degree]
+            .inverse()
+            .expect("leading coefficient is never zero");
+
+        let mut remainder = self.coefficients.clone();
+        if remainder.len() <= divisor_degree {
+            return (Polynomial::zero(), self.clone());
+        }
+
+        let mut quotient = vec![Gf128::zero(); remainder.len() - divisor_degree];
+        for shift in (0..quotient.len()).rev() {
+            let factor = remainder[shift + divisor_degree] * leading_inverse;
+            quotient[shift] = factor;
+            for (i, coefficient) in divisor.coefficients.iter().enumerate() {
+                remainder[shift + i] += factor * *coefficient;
+            }
+        }
+
+        (Polynomial::new(quotient), Polynomial::new(remainder))
+    }
+
+    /// The monic greatest common divisor of two polynomials
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        let mut a = self.clone();
+        let mut b = other.clone();
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b);
+            a = b;
+            b = remainder;
+        }
+        a.monic()
+    }
+
+    /// The formal derivative; in characteristic 2 every even degree term vanishes
+    pub fn derivative(&self) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(degree, coefficient)| {
+                    if degree % 2 == 1 {
+                        *coefficient
+                    } else {
+                        Gf128::zero()
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Finds every root of the polynomial in GF(2^128)
+    ///
+    /// The polynomial is reduced to its square free part, the product of its linear factors is
+    /// split out by distinct degree factorisation and those are separated with Cantor-Zassenhaus
+    pub fn roots(&self) -> Vec<Gf128> {
+        if self.degree().unwrap_or(0) == 0 {
+            return Vec::new();
+        }
+
+        self.square_free_factorisation()
+            .into_iter()
+            .flat_map(|(factor, _)| factor.distinct_degree_factorisation())
+            .filter(|(_, degree)| *degree == 1)
+            .flat_map(|(factor, _)| factor.equal_degree_factorisation(1))
+            // a monic linear factor x + c has the root c
+            .map(|factor| factor.coefficients[0])
+            .collect()
+    }
+
+    /// Splits the polynomial into monic square free factors along with their multiplicity
+    ///
+    /// Constant factors are discarded
+    pub fn square_free_factorisation(&self) -> Vec<(Polynomial, usize)> {
+        let polynomial = self.monic();
+        if polynomial.degree().unwrap_or(0) == 0 {
+            return Vec::new();
+        }
+
+        let mut factors = Vec::new();
+        let mut c = polynomial.gcd(&polynomial.derivative());
+        let mut w = polynomial.div_rem(&c).0;
+        let mut multiplicity = 1;
+        while !w.is_one() {
+            let y = w.gcd(&c);
+            let factor = w.div_rem(&y).0;
+            if !factor.is_one() {
+                factors.push((factor, multiplicity));
+            }
+            w = y;
+            c = c.div_rem(&w).0;
+            multiplicity += 1;
+        }
+
+        // whatever is left is a perfect square: take its square root and recurse
+        if !c.is_one() {
+            factors.extend(
+                c.sqrt()
+                    .square_free_factorisation()
+                    .into_iter()
+                    .map(|(factor, multiplicity)| (factor, multiplicity * 2)),
+            );
+        }
+
+        factors
+    }
+
+    /// Splits a monic square free polynomial into factors each of which is the product of all
+    /// the irreducible factors of one degree; the degree is returned alongside each factor
+    pub fn distinct_degree_factorisation(&self) -> Vec<(Polynomial, usize)> {
+        let mut factors = Vec::new();
+        let mut remaining = self.monic();
+        // x^(q^degree) mod remaining
+        let mut frobenius = Polynomial::x();
+        let mut degree = 1;
+
+        while remaining.degree().unwrap_or(0) >= 2 * degree {
+            for _ in 0..FIELD_DEGREE {
+                frobenius = frobenius.square_rem(&remaining);
+            }
+            let factor = remaining.gcd(&(&frobenius + &Polynomial::x()));
+            if !factor.is_one() {
+                remaining = remaining.div_rem(&factor).0;
+                frobenius = frobenius.div_rem(&remaining).1;
+                factors.push((factor, degree));
+            }
+            degree += 1;
+        }
+
+        if let Some(remaining_degree) = remaining.degree() {
+            if remaining_degree > 0 {
+                factors.push((remaining, remaining_degree));
+            }
+        }
+
+        factors
+    }
+
+    /// Cantor-Zassenhaus: splits a monic square free polynomial whose irreducible factors all
+    /// have the given degree into those factors
+    ///
+    /// In characteristic 2 the usual (q^d - 1) / 2 power is replaced by the trace map
+    /// a + a^2 + a^4 + ... + a^(2^(128d - 1)), which is 0 or 1 modulo each irreducible factor
+    /// and so splits the factors roughly in half on each random choice of a
+    pub fn equal_degree_factorisation(&self, degree: usize) -> Vec<Polynomial> {
+        let polynomial = self.monic();
+        let total_degree = polynomial.degree().unwrap_or(0);
+        if total_degree == 0 || degree == 0 {
+            return Vec::new();
+        }
+
+        let factor_count = total_degree / degree;
+        let mut factors = vec![polynomial.clone()];
+        while factors.len() < factor_count {
+            let random = Polynomial::new((0..total_degree).map(|_| Gf128::random()).collect());
+
+            let mut trace = random.div_rem(&polynomial).1;
+            let mut power = trace.clone();
+            for _ in 1..(FIELD_DEGREE * degree) {
+                power = power.square_rem(&polynomial);
+                trace = &trace + &power;
+            }
+
+            factors = factors
+                .into_iter()
+                .flat_map(|factor| {
+                    if factor.degree() == Some(degree) {
+                        return vec![factor];
+                    }
+                    let split = factor.gcd(&trace);
+                    if split.is_one() || split == factor {
+                        vec![factor]
+                    } else {
+                        let other = factor.div_rem(&split).0;
+                        vec![split, other]
+                    }
+                })
+                .collect();
+        }
+
+        factors
+    }
+
+    /// The square root of a polynomial that is a perfect square
+    ///
+    /// In characteristic 2 (sum a_i x^i)^2 = sum a_i^2 x^2i, so the odd coefficients are
+    /// ignored and every even coefficient is square rooted
+    fn sqrt(&self) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .step_by(2)
+                .map(|coefficient| coefficient.sqrt())
+                .collect(),
+        )
+    }
+
+    fn square_rem(&self, modulus: &Polynomial) -> Polynomial {
+        (self * self).div_rem(modulus).1
+    }
+
+    fn scale(&self, factor: Gf128) -> Polynomial {
+        Polynomial::new(
+            self.coefficients
+                .iter()
+                .map(|coefficient| *coefficient * factor)
+                .collect(),
+        )
+    }
+
+    fn trim(&mut self) {
+        while self.coefficients.last().is_some_and(Gf128::is_zero) {
+            self.coefficients.pop();
+        }
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let (longer, shorter) = if self.coefficients.len() >= rhs.coefficients.len() {
+            (self, rhs)
+        } else {
+            (rhs, self)
+        };
+
+        let mut coefficients = longer.coefficients.clone();
+        coefficients
+            .iter_mut()
+            .zip(shorter.coefficients.iter())
+            .for_each(|(x, y)| *x += *y);
+        Polynomial::new(coefficients)
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if self.is_zero() || rhs.is_zero() {
+            return Polynomial::zero();
+        }
+
+        let mut coefficients =
+            vec![Gf128::zero(); self.coefficients.len() + rhs.coefficients.len() - 1];
+        for (i, x) in self.coefficients.iter().enumerate() {
+            for (j, y) in rhs.coefficients.iter().enumerate() {
+                coefficients[i + j] += *x * *y;
+            }
+        }
+        Polynomial::new(coefficients)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::gf128::polynomial::Polynomial;
+    use crate::cyphers::gf128::Gf128;
+
+    // x + root
+    fn linear(root: Gf128) -> Polynomial {
+        Polynomial::new(vec![root, Gf128::one()])
+    }
+
+    #[test]
+    fn division_reverses_multiplication() {
+        let a = Polynomial::new(vec![Gf128::random(), Gf128::random(), Gf128::random()]);
+        let b = Polynomial::new(vec![Gf128::random(), Gf128::one()]);
+        let remainder = Polynomial::new(vec![Gf128::random()]);
+
+        let product = &(&a * &b) + &remainder;
+        let (quotient, calculated_remainder) = product.div_rem(&b);
+
+        assert_eq!(a, quotient);
+        assert_eq!(remainder, calculated_remainder);
+    }
+
+    #[test]
+    fn gcd_finds_common_factor() {
+        let common = linear(Gf128::random());
+        let a = &common * &linear(Gf128::random());
+        let b = &common * &linear(Gf128::random());
+
+        assert_eq!(common, a.gcd(&b));
+    }
+
+    #[test]
+    fn roots_of_product_of_linear_factors_are_found() {
+        let roots: Vec<Gf128> = (0..4).map(|_| Gf128::random()).collect();
+        let polynomial = roots
+            .iter()
+            .fold(Polynomial::one(), |accum, root| &accum * &linear(*root));
+
+        let mut found = polynomial.roots();
+        found.sort_by_key(|root| root.to_block());
+        let mut expected = roots.clone();
+        expected.sort_by_key(|root| root.to_block());
+
+        assert_eq!(expected, found);
+        found
+            .iter()
+            .for_each(|root| assert!(polynomial.evaluate(*root).is_zero()));
+    }
+
+    #[test]
+    fn repeated_and_irreducible_factors_are_handled() {
+        let root = Gf128::random();
+        let squared = &linear(root) * &linear(root);
+        // x^2 + x + c with c random is irreducible about half the time; multiplying by an
+        // irreducible quadratic must not introduce any extra roots
+        let quadratic = loop {
+            let candidate = Polynomial::new(vec![Gf128::random(), Gf128::one(), Gf128::one()]);
+            if candidate.roots().is_empty() {
+                break candidate;
+            }
+        };
+        let polynomial = &squared * &quadratic;
+
+        let factors = polynomial.square_free_factorisation();
+        assert!(factors.contains(&(linear(root), 2)));
+
+        assert_eq!(vec![root], polynomial.roots());
+    }
+}
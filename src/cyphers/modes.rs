@@ -0,0 +1,44 @@
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+
+pub mod cbc;
+pub mod cfb;
+pub mod ctr;
+pub mod ecb;
+pub mod ofb;
+pub mod pcbc;
+
+#[derive(Debug, PartialEq)]
+enum ModeError {
+    InvalidCipherTextLength { length: usize, block_size: usize },
+    InvalidNonceLength { length: usize, block_size: usize },
+    CounterOverflow,
+    TooShortForStealing { length: usize, block_size: usize },
+    UnsupportedBlockSize { expected: usize, actual: usize },
+}
+
+impl Display for ModeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModeError::InvalidCipherTextLength { length, block_size } => write!(
+                f,
+                "cipher text length {length} is not a multiple of the block size {block_size}"
+            ),
+            ModeError::InvalidNonceLength { length, block_size } => write!(
+                f,
+                "a {length} byte nonce leaves no room for a counter in a {block_size} byte block"
+            ),
+            ModeError::CounterOverflow => write!(f, "the counter overflowed"),
+            ModeError::TooShortForStealing { length, block_size } => write!(
+                f,
+                "ciphertext stealing needs at least one {block_size} byte block, found {length} bytes"
+            ),
+            ModeError::UnsupportedBlockSize { expected, actual } => write!(
+                f,
+                "expected a cipher with a {expected} byte block, found a {actual} byte block"
+            ),
+        }
+    }
+}
+
+impl StdError for ModeError {}
@@ -0,0 +1,576 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::modes::ModeError;
+use crate::cyphers::padding::pkcs7::Pkcs7;
+use crate::cyphers::padding::Padding;
+use crate::Error;
+
+/// Cipher block chaining mode: each plain text block is xor'd with the previous cipher text block
+/// (or the iv for the first block) before it is encrypted
+///
+/// Plain text is padded with pkcs#7 unless another padding scheme is chosen
+pub struct Cbc<C: BlockCipher, P: Padding = Pkcs7> {
+    cipher: C,
+    padding: P,
+}
+
+impl<C: BlockCipher> Cbc<C> {
+    pub fn new(cipher: C) -> Cbc<C> {
+        Cbc::with_padding(cipher, Pkcs7)
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Cbc<C, P> {
+    pub fn with_padding(cipher: C, padding: P) -> Cbc<C, P> {
+        Cbc { cipher, padding }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Pads the plain text and encrypts it
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn encrypt(&self, plain_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let mut writer =
+            CbcEncryptWriter::with_padding(&self.cipher, iv, Vec::new(), &self.padding);
+        writer
+            .write_all(plain_text)
+            .expect("writing to a vec never fails");
+        writer.finish().expect("writing to a vec never fails")
+    }
+
+    /// Decrypts the cipher text; padding is left in place for the caller to validate
+    ///
+    /// Errors
+    /// If the cipher text is not a whole number of blocks
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn decrypt(&self, cipher_text: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+        let block_size = self.cipher.block_size();
+        if !cipher_text.len().is_multiple_of(block_size) {
+            return Err(Box::new(ModeError::InvalidCipherTextLength {
+                length: cipher_text.len(),
+                block_size,
+            }));
+        }
+
+        let mut plain_text = Vec::with_capacity(cipher_text.len());
+        CbcDecryptReader::new(&self.cipher, iv, cipher_text)
+            .read_to_end(&mut plain_text)
+            .expect("reading whole blocks from a slice never fails");
+        Ok(plain_text)
+    }
+
+    /// Decrypts the cipher text and removes the padding
+    ///
+    /// Errors
+    /// If the cipher text is not a whole number of blocks
+    /// If the decrypted plain text does not end with valid padding
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn try_decrypt(&self, cipher_text: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+        let plain_text = self.decrypt(cipher_text, iv)?;
+        Ok(self.padding.unpad(&plain_text, self.cipher.block_size())?)
+    }
+
+    /// Encrypts the plain text using ciphertext stealing instead of padding, so the cipher text is
+    /// the same length as the plain text
+    ///
+    /// The final partial block is zero padded and the whole message CBC encrypted, then the
+    /// padding bytes are dropped from the end of the second to last cipher text block; the
+    /// variant decides the order of the last two blocks
+    ///
+    /// Errors
+    /// If the plain text is shorter than one block
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn encrypt_cts(
+        &self,
+        plain_text: &[u8],
+        iv: &[u8],
+        variant: CiphertextStealing,
+    ) -> Result<Vec<u8>, Error> {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+        if plain_text.len() < block_size {
+            return Err(Box::new(ModeError::TooShortForStealing {
+                length: plain_text.len(),
+                block_size,
+            }));
+        }
+
+        let tail_length = tail_length(plain_text.len(), block_size);
+        let mut cipher_text = plain_text.to_vec();
+        cipher_text.resize(plain_text.len().next_multiple_of(block_size), 0);
+
+        let mut last_block = iv.to_vec();
+        cipher_text.chunks_mut(block_size).for_each(|block| {
+            block.iter_mut().zip(&last_block).for_each(|(x, y)| *x ^= y);
+            self.cipher.encrypt_block(block);
+            last_block.copy_from_slice(block);
+        });
+
+        if cipher_text.len() == block_size {
+            return Ok(cipher_text);
+        }
+
+        // the last block in full followed by the start of the second to last block
+        let split = cipher_text.len() - 2 * block_size;
+        let mut swapped = cipher_text[split + block_size..].to_vec();
+        swapped.extend_from_slice(&cipher_text[split..split + tail_length]);
+        cipher_text.truncate(split);
+
+        if variant.swaps_last_blocks(tail_length == block_size) {
+            cipher_text.extend(swapped);
+        } else {
+            let (last, second_to_last) = swapped.split_at(block_size);
+            cipher_text.extend_from_slice(second_to_last);
+            cipher_text.extend_from_slice(last);
+        }
+
+        Ok(cipher_text)
+    }
+
+    /// Decrypts a cipher text produced by [Cbc::encrypt_cts] with the same variant
+    ///
+    /// Errors
+    /// If the cipher text is shorter than one block
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn decrypt_cts(
+        &self,
+        cipher_text: &[u8],
+        iv: &[u8],
+        variant: CiphertextStealing,
+    ) -> Result<Vec<u8>, Error> {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+        if cipher_text.len() < block_size {
+            return Err(Box::new(ModeError::TooShortForStealing {
+                length: cipher_text.len(),
+                block_size,
+            }));
+        }
+        if cipher_text.len() == block_size {
+            return self.decrypt(cipher_text, iv);
+        }
+
+        let tail_length = tail_length(cipher_text.len(), block_size);
+        let split = cipher_text.len() - block_size - tail_length;
+        let (head, tail) = cipher_text.split_at(split);
+        let (partial, last) = if variant.swaps_last_blocks(tail_length == block_size) {
+            let (last, partial) = tail.split_at(block_size);
+            (partial, last)
+        } else {
+            tail.split_at(tail_length)
+        };
+
+        // the last block was encrypted from the zero padded final block, so decrypting it gives
+        // the dropped tail of the second to last block in place of the padding
+        let mut decrypted_last = last.to_vec();
+        self.cipher.decrypt_block(&mut decrypted_last);
+
+        let mut rebuilt = head.to_vec();
+        rebuilt.extend_from_slice(partial);
+        rebuilt.extend_from_slice(&decrypted_last[tail_length..]);
+        rebuilt.extend_from_slice(last);
+
+        let mut plain_text = self.decrypt(&rebuilt, iv)?;
+        plain_text.truncate(cipher_text.len());
+        Ok(plain_text)
+    }
+}
+
+/// The three ciphertext stealing layouts from the addendum to NIST SP 800-38A
+///
+/// They differ only in the order of the last two cipher text blocks. CS1 keeps the CBC order,
+/// with the truncated second to last block before the last block. CS3 always swaps them, as
+/// Kerberos (RFC 3962) does. CS2 swaps them only when the plain text isn't a whole number of
+/// blocks, so whole block messages are plain CBC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiphertextStealing {
+    CS1,
+    CS2,
+    CS3,
+}
+
+impl CiphertextStealing {
+    fn swaps_last_blocks(&self, whole_blocks: bool) -> bool {
+        match self {
+            CiphertextStealing::CS1 => false,
+            CiphertextStealing::CS2 => !whole_blocks,
+            CiphertextStealing::CS3 => true,
+        }
+    }
+}
+
+/// The length of the final, possibly partial, block of a message of at least one block
+fn tail_length(length: usize, block_size: usize) -> usize {
+    match length % block_size {
+        0 => block_size,
+        remainder => remainder,
+    }
+}
+
+/// Encrypts everything written to it in CBC mode and writes the cipher text to the inner writer
+///
+/// Whole blocks are encrypted and passed on as soon as they are written; any partial block is
+/// buffered until more data arrives. The padding (pkcs#7 unless another scheme is chosen) is only
+/// added by [CbcEncryptWriter::finish], which must be called to write the final block.
+///
+/// Cipher text the inner writer hasn't taken yet is kept, and passed on before anything else by
+/// the next write, flush or finish, so a write that fails can be retried without losing or
+/// repeating output
+pub struct CbcEncryptWriter<C: BlockCipher, W: Write, P: Padding = Pkcs7> {
+    cipher: C,
+    inner: W,
+    padding: P,
+    last_block: Vec<u8>,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl<C: BlockCipher, W: Write> CbcEncryptWriter<C, W> {
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn new(cipher: C, iv: &[u8], inner: W) -> CbcEncryptWriter<C, W> {
+        CbcEncryptWriter::with_padding(cipher, iv, inner, Pkcs7)
+    }
+}
+
+impl<C: BlockCipher, W: Write, P: Padding> CbcEncryptWriter<C, W, P> {
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn with_padding(cipher: C, iv: &[u8], inner: W, padding: P) -> CbcEncryptWriter<C, W, P> {
+        assert_eq!(cipher.block_size(), iv.len(), "iv must be one block long");
+        CbcEncryptWriter {
+            cipher,
+            inner,
+            padding,
+            last_block: iv.to_vec(),
+            buffer: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Pads and encrypts the remaining buffered plain text, flushes the inner writer and returns it
+    pub fn finish(mut self) -> io::Result<W> {
+        let padded = self.padding.pad(&self.buffer, self.cipher.block_size());
+        self.encrypt_blocks(&padded);
+        self.write_pending()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Passes the pending cipher text on to the inner writer, keeping any it doesn't take
+    fn write_pending(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.inner.write(&self.pending) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Encrypts whole blocks chained from the last block onto the pending cipher text
+    fn encrypt_blocks(&mut self, plain_text: &[u8]) {
+        plain_text
+            .chunks(self.cipher.block_size())
+            .for_each(|block| {
+                self.last_block
+                    .iter_mut()
+                    .zip(block)
+                    .for_each(|(x, y)| *x ^= y);
+                self.cipher.encrypt_block(&mut self.last_block);
+                self.pending.extend_from_slice(&self.last_block);
+            });
+    }
+}
+
+impl<C: BlockCipher, W: Write, P: Padding> Write for CbcEncryptWriter<C, W, P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // nothing is taken from buf until the cipher text of earlier writes is through
+        self.write_pending()?;
+
+        let block_size = self.cipher.block_size();
+        self.buffer.extend_from_slice(buf);
+        let whole_blocks = self.buffer.len() - self.buffer.len() % block_size;
+        let plain_text: Vec<u8> = self.buffer.drain(..whole_blocks).collect();
+        self.encrypt_blocks(&plain_text);
+
+        // buf has been taken either way; if this fails the next write, flush or finish retries it
+        let _ = self.write_pending();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
+
+/// Decrypts the cipher text read from the inner reader in CBC mode
+///
+/// Cipher text is read and decrypted a block at a time, so wrap unbuffered sources in a
+/// [std::io::BufReader]. Padding is left in place for the caller to validate. Cipher text that
+/// ends part way through a block is an [ErrorKind::InvalidData] error
+pub struct CbcDecryptReader<C: BlockCipher, R: Read> {
+    cipher: C,
+    inner: R,
+    last_block: Vec<u8>,
+    block: Vec<u8>,
+    plain_text: Vec<u8>,
+    position: usize,
+    length: usize,
+    finished: bool,
+}
+
+impl<C: BlockCipher, R: Read> CbcDecryptReader<C, R> {
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn new(cipher: C, iv: &[u8], inner: R) -> CbcDecryptReader<C, R> {
+        assert_eq!(cipher.block_size(), iv.len(), "iv must be one block long");
+        let block_size = cipher.block_size();
+        CbcDecryptReader {
+            cipher,
+            inner,
+            last_block: iv.to_vec(),
+            block: Vec::with_capacity(block_size),
+            plain_text: Vec::with_capacity(block_size),
+            position: 0,
+            length: 0,
+            finished: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads up to the next whole block of cipher text and decrypts it
+    fn decrypt_next_block(&mut self) -> io::Result<()> {
+        let block_size = self.cipher.block_size();
+        self.block.clear();
+
+        let mut chunk = [0; 64];
+        while self.block.len() < block_size {
+            let wanted = (block_size - self.block.len()).min(chunk.len());
+            match self.inner.read(&mut chunk[..wanted]) {
+                Ok(0) => break,
+                Ok(read) => self.block.extend_from_slice(&chunk[..read]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.length += self.block.len();
+        if self.block.len() < block_size {
+            self.finished = true;
+            if self.block.is_empty() {
+                return Ok(());
+            }
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                ModeError::InvalidCipherTextLength {
+                    length: self.length,
+                    block_size,
+                },
+            ));
+        }
+
+        let mut block = self.block.clone();
+        self.cipher.decrypt_block(&mut block);
+        block
+            .iter_mut()
+            .zip(&self.last_block)
+            .for_each(|(x, y)| *x ^= y);
+
+        self.last_block.copy_from_slice(&self.block);
+        self.plain_text = block;
+        self.position = 0;
+        Ok(())
+    }
+}
+
+impl<C: BlockCipher, R: Read> Read for CbcDecryptReader<C, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plain_text.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.decrypt_next_block()?;
+        }
+
+        let available = &self.plain_text[self.position..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::cbc::{Cbc, CbcDecryptReader, CbcEncryptWriter, CiphertextStealing};
+    use crate::cyphers::padding::iso7816::Iso7816;
+    use crate::cyphers::padding::pkcs7;
+    use crate::test_util::FlakyWriter;
+
+    #[test]
+    fn round_trip_with_eight_byte_blocks() {
+        let cbc = Cbc::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let plain_text = "A".repeat(16);
+        let iv = [7; 8];
+
+        let encrypted = cbc.encrypt(plain_text.as_bytes(), &iv);
+        // chaining hides the repeated plain text block
+        assert_ne!(encrypted[..8], encrypted[8..16]);
+
+        let decrypted = cbc.decrypt(&encrypted, &iv).unwrap();
+        assert_eq!(
+            plain_text.as_bytes(),
+            pkcs7::try_unpad(&decrypted, 8).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip_with_aes_256() {
+        let cbc = Cbc::new(Aes::new(&[3; 32]).unwrap());
+        let plain_text = "a message that is longer than a single block".as_bytes();
+        let iv = [9; 16];
+
+        let encrypted = cbc.encrypt(plain_text, &iv);
+        assert_eq!(48, encrypted.len());
+
+        let decrypted = cbc.decrypt(&encrypted, &iv).unwrap();
+        assert_eq!(plain_text, pkcs7::try_unpad(&decrypted, 16).unwrap());
+    }
+
+    #[test]
+    fn failed_write_loses_nothing() {
+        let cipher = Aes::new(&[4; 16]).unwrap();
+        let iv = [6; 16];
+        let plain_text = "retried after the inner writer fails part way".repeat(3);
+
+        // fails outright, then part way through a write_all
+        for (chunk, fail_on) in [(usize::MAX, 2), (5, 4)] {
+            let mut writer = CbcEncryptWriter::new(&cipher, &iv, FlakyWriter::new(chunk, fail_on));
+            for chunk in plain_text.as_bytes().chunks(20) {
+                while writer.write(chunk).is_err() {}
+            }
+            let inner = writer.finish().unwrap();
+
+            assert!(inner.failed());
+            assert_eq!(
+                Cbc::new(&cipher).encrypt(plain_text.as_bytes(), &iv),
+                inner.written
+            );
+        }
+    }
+
+    #[test]
+    fn streaming_matches_slice_encryption() {
+        let cipher = Aes::new(&[4; 16]).unwrap();
+        let iv = [6; 16];
+        let plain_text = "written to the stream in awkwardly sized pieces".repeat(5);
+
+        let mut writer = CbcEncryptWriter::new(&cipher, &iv, Vec::new());
+        plain_text
+            .as_bytes()
+            .chunks(7)
+            .for_each(|chunk| writer.write_all(chunk).unwrap());
+        let encrypted = writer.finish().unwrap();
+        assert_eq!(
+            Cbc::new(&cipher).encrypt(plain_text.as_bytes(), &iv),
+            encrypted
+        );
+
+        let mut reader = CbcDecryptReader::new(&cipher, &iv, encrypted.as_slice());
+        let mut decrypted = Vec::new();
+        let mut buf = [0; 5];
+        loop {
+            let read = reader.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            decrypted.extend_from_slice(&buf[..read]);
+        }
+        assert_eq!(
+            plain_text.as_bytes(),
+            pkcs7::try_unpad(&decrypted, 16).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_cipher_text_is_rejected() {
+        let cbc = Cbc::new(Aes::new(&[3; 16]).unwrap());
+        let iv = [9; 16];
+        let mut encrypted = cbc.encrypt("some message".as_bytes(), &iv);
+        encrypted.pop();
+
+        assert!(cbc.decrypt(&encrypted, &iv).is_err());
+        let mut decrypted = Vec::new();
+        assert!(
+            CbcDecryptReader::new(&cbc.cipher, &iv, encrypted.as_slice())
+                .read_to_end(&mut decrypted)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ciphertext_stealing_round_trips_every_length() {
+        let cbc = Cbc::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let iv = [7; 8];
+        let plain_text: Vec<u8> = (0..40).collect();
+
+        for variant in [
+            CiphertextStealing::CS1,
+            CiphertextStealing::CS2,
+            CiphertextStealing::CS3,
+        ] {
+            for length in 8..=plain_text.len() {
+                let encrypted = cbc
+                    .encrypt_cts(&plain_text[..length], &iv, variant)
+                    .unwrap();
+                assert_eq!(length, encrypted.len());
+                assert_eq!(
+                    plain_text[..length],
+                    cbc.decrypt_cts(&encrypted, &iv, variant).unwrap()
+                );
+            }
+
+            assert!(cbc.encrypt_cts(&plain_text[..7], &iv, variant).is_err());
+            assert!(cbc.decrypt_cts(&plain_text[..7], &iv, variant).is_err());
+        }
+    }
+
+    #[test]
+    fn chosen_padding_is_applied() {
+        let cbc = Cbc::with_padding(Aes::new(&[3; 16]).unwrap(), Iso7816);
+        let iv = [9; 16];
+
+        let encrypted = cbc.encrypt("fourteen bytes".as_bytes(), &iv);
+        assert_eq!([0x80, 0], cbc.decrypt(&encrypted, &iv).unwrap()[14..]);
+        assert_eq!(
+            "fourteen bytes".as_bytes(),
+            cbc.try_decrypt(&encrypted, &iv).unwrap()
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::cyphers::block_cipher::BlockCipher;
+
+/// Cipher feedback mode: the previous cipher text is fed back through the cipher to produce the
+/// key stream for the next segment of plain text
+///
+/// The segment size is the number of bytes encrypted per call to the cipher; a segment size of
+/// one block is full-block CFB and a segment size of one byte is CFB-8. No padding is needed, a
+/// short final segment uses only part of the key stream
+pub struct Cfb<C: BlockCipher> {
+    cipher: C,
+    segment_size: usize,
+}
+
+impl<C: BlockCipher> Cfb<C> {
+    /// Returns a full-block Cfb
+    pub fn new(cipher: C) -> Cfb<C> {
+        let segment_size = cipher.block_size();
+        Cfb {
+            cipher,
+            segment_size,
+        }
+    }
+
+    /// Returns a Cfb encrypting segment_size bytes per block encryption
+    ///
+    /// Panics
+    /// If the segment size is zero or larger than the cipher's block size
+    pub fn with_segment_size(cipher: C, segment_size: usize) -> Cfb<C> {
+        assert!(
+            segment_size > 0 && segment_size <= cipher.block_size(),
+            "segment size must be between one byte and one block"
+        );
+        Cfb {
+            cipher,
+            segment_size,
+        }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    pub fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn encrypt(&self, plain_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        self.apply(plain_text, iv, |_, output| output)
+    }
+
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn decrypt(&self, cipher_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        self.apply(cipher_text, iv, |input, _| input)
+    }
+
+    /// Xors each segment of the input with the key stream, choosing which of the input and
+    /// output segments is the cipher text to feed back into the shift register
+    fn apply<F>(&self, data: &[u8], iv: &[u8], feedback: F) -> Vec<u8>
+    where
+        F: for<'a> Fn(&'a [u8], &'a [u8]) -> &'a [u8],
+    {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+
+        let mut register = iv.to_vec();
+        let mut result = Vec::with_capacity(data.len());
+        for segment in data.chunks(self.segment_size) {
+            let mut key_stream = register.clone();
+            self.cipher.encrypt_block(&mut key_stream);
+            let output: Vec<u8> = segment.iter().zip(key_stream).map(|(x, y)| x ^ y).collect();
+
+            register.rotate_left(self.segment_size);
+            register[block_size - self.segment_size..][..segment.len()]
+                .copy_from_slice(feedback(segment, &output));
+            result.extend(output);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::cfb::Cfb;
+
+    #[test]
+    fn round_trip_with_eight_byte_blocks() {
+        let iv = [5; 8];
+        let plain_text = "cfb needs no padding at all".as_bytes();
+
+        for segment_size in [1, 3, 8] {
+            let cfb =
+                Cfb::with_segment_size(Feistel::new("YELLOW SUBMARINE".as_bytes()), segment_size);
+            let encrypted = cfb.encrypt(plain_text, &iv);
+            assert_eq!(plain_text.len(), encrypted.len());
+            assert_eq!(plain_text, cfb.decrypt(&encrypted, &iv));
+        }
+    }
+
+    #[test]
+    fn bit_flip_garbles_following_block() {
+        let cfb = Cfb::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let iv = [1; 8];
+        let plain_text = [0; 32];
+
+        let mut encrypted = cfb.encrypt(&plain_text, &iv);
+        encrypted[1] ^= 4;
+        let decrypted = cfb.decrypt(&encrypted, &iv);
+
+        // the flipped bit is copied into the plain text, the next block is garbled and the
+        // cipher recovers after that
+        assert_eq!(4, decrypted[1]);
+        assert_ne!(plain_text[8..16], decrypted[8..16]);
+        assert_eq!(plain_text[16..], decrypted[16..]);
+    }
+}
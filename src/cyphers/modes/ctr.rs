@@ -0,0 +1,590 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::modes::ModeError;
+use crate::Error;
+
+/// The number of blocks of key stream each parallel task generates
+#[cfg(feature = "parallel")]
+const BLOCKS_PER_TASK: usize = 1024;
+
+/// Counter mode: turns a block cipher into a stream cipher by encrypting successive counter blocks
+///
+/// The counter block is the little endian nonce in the first half of the block followed by the
+/// little endian block count in the second half; for a 16 byte block that is a 64 bit nonce and a
+/// 64 bit count. Each value is truncated to fit half a block. Other layouts are available through
+/// [CtrKeystream]
+pub struct Ctr<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Ctr<C> {
+    pub fn new(cipher: C) -> Ctr<C> {
+        Ctr { cipher }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Xors the data with the key stream for the given nonce
+    ///
+    /// Encryption and decryption are the same operation
+    pub fn apply_keystream(&self, data: &[u8], nonce: u64) -> Vec<u8> {
+        let mut stream = CtrStream::new(&self.cipher, nonce, Vec::with_capacity(data.len()));
+        stream
+            .write_all(data)
+            .expect("writing to a vec never fails");
+        stream.into_inner()
+    }
+
+    /// Xors the data with the key stream for the given nonce, spreading the blocks across all
+    /// cores
+    ///
+    /// Produces the same output as [Ctr::apply_keystream]
+    #[cfg(feature = "parallel")]
+    pub fn par_apply_keystream(&self, data: &[u8], nonce: u64) -> Vec<u8>
+    where
+        C: Sync,
+    {
+        let mut output = data.to_vec();
+        self.keystream(nonce)
+            .par_apply_keystream(&mut output)
+            .expect("the counter wraps instead of overflowing");
+        output
+    }
+
+    /// Returns the block of key stream for the given nonce and block count
+    pub fn key_stream_block(&self, nonce: u64, count: u64) -> Vec<u8> {
+        self.keystream(nonce)
+            .key_stream_block(count)
+            .expect("the block count is truncated to fit the counter")
+    }
+
+    /// Returns the seekable key stream for the given nonce
+    pub fn keystream(&self, nonce: u64) -> CtrKeystream<&C> {
+        CtrKeystream::with_layout(&self.cipher, nonce, self.cipher.block_size() / 2)
+    }
+}
+
+/// The byte order of the counter within the counter block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+/// A random access counter mode key stream
+///
+/// Each counter block is a fixed nonce followed by a counter filling the rest of the block, so a
+/// 16 byte block with a 4, 8 or 12 byte nonce leaves a 96, 64 or 32 bit counter. The counter
+/// starts at an initial value (zero by default) and increases by one per block. NIST SP 800-38A
+/// and RFC 3686 use a big endian counter; the cryptopals challenges use a little endian one.
+///
+/// Running past the largest counter value is an error unless wrapping is enabled, in which case
+/// the counter wraps to zero without changing the nonce (as inc32 does in GCM)
+#[derive(Debug, Clone)]
+pub struct CtrKeystream<C: BlockCipher> {
+    cipher: C,
+    nonce: Vec<u8>,
+    endianness: Endianness,
+    initial_counter: u128,
+    wrapping: bool,
+    position: u64,
+    block: Vec<u8>,
+    block_index: Option<u64>,
+}
+
+impl<C: BlockCipher> CtrKeystream<C> {
+    /// Returns a key stream with the given nonce at the start of each counter block and a
+    /// counter starting at zero
+    ///
+    /// Errors
+    /// If the nonce does not leave at least one byte of the block for the counter
+    pub fn new(cipher: C, nonce: &[u8], endianness: Endianness) -> Result<CtrKeystream<C>, Error> {
+        let block_size = cipher.block_size();
+        if nonce.len() >= block_size {
+            return Err(Box::new(ModeError::InvalidNonceLength {
+                length: nonce.len(),
+                block_size,
+            }));
+        }
+
+        Ok(CtrKeystream {
+            cipher,
+            nonce: nonce.to_vec(),
+            endianness,
+            initial_counter: 0,
+            wrapping: false,
+            position: 0,
+            block: Vec::with_capacity(block_size),
+            block_index: None,
+        })
+    }
+
+    /// The RFC 3686 layout: a 32 bit nonce and 64 bit iv followed by a 32 bit big endian counter
+    /// starting at one
+    ///
+    /// Errors
+    /// If the cipher does not have a 16 byte block
+    pub fn rfc3686(cipher: C, nonce: [u8; 4], iv: [u8; 8]) -> Result<CtrKeystream<C>, Error> {
+        if cipher.block_size() != 16 {
+            return Err(Box::new(ModeError::UnsupportedBlockSize {
+                expected: 16,
+                actual: cipher.block_size(),
+            }));
+        }
+        CtrKeystream::new(cipher, &[&nonce[..], &iv[..]].concat(), Endianness::Big)?
+            .with_initial_counter(1)
+    }
+
+    /// The layout used by [Ctr]: the nonce and block count, both little endian, each take up half
+    /// the block and are truncated to fit
+    fn with_layout(cipher: C, nonce: u64, nonce_length: usize) -> CtrKeystream<C> {
+        let nonce_length = nonce_length.min(8);
+        CtrKeystream::new(
+            cipher,
+            &nonce.to_le_bytes()[..nonce_length],
+            Endianness::Little,
+        )
+        .expect("half a block leaves room for the counter")
+        .wrapping()
+    }
+
+    /// Sets the counter value used for the first block
+    ///
+    /// Errors
+    /// If the value does not fit in the counter
+    pub fn with_initial_counter(mut self, initial_counter: u128) -> Result<CtrKeystream<C>, Error> {
+        if initial_counter > self.max_counter() {
+            return Err(Box::new(ModeError::CounterOverflow));
+        }
+        self.initial_counter = initial_counter;
+        self.block_index = None;
+        Ok(self)
+    }
+
+    /// Lets the counter wrap around to zero instead of returning an error when it overflows
+    pub fn wrapping(mut self) -> CtrKeystream<C> {
+        self.wrapping = true;
+        self
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// The byte offset in the key stream that the next byte will be taken from
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves to the given byte offset in the key stream
+    pub fn seek(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    /// Xors the data with the key stream starting at the current position, advancing past it
+    ///
+    /// Encryption and decryption are the same operation
+    ///
+    /// Errors
+    /// If the counter would overflow; the data and position are left unchanged
+    pub fn apply_keystream(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let block_size = self.cipher.block_size() as u64;
+        let end = self
+            .position
+            .checked_add(data.len() as u64)
+            .ok_or(ModeError::CounterOverflow)?;
+        self.counter((end - 1) / block_size)?;
+
+        for byte in data.iter_mut() {
+            let index = self.position / block_size;
+            if self.block_index != Some(index) {
+                self.block = self.key_stream_block(index)?;
+                self.block_index = Some(index);
+            }
+            *byte ^= self.block[(self.position % block_size) as usize];
+            self.position += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Xors the data with the key stream starting at the current position, advancing past it,
+    /// with the whole blocks split between threads
+    ///
+    /// Produces the same output as [CtrKeystream::apply_keystream]
+    ///
+    /// Errors
+    /// If the counter would overflow; the data and position are left unchanged
+    #[cfg(feature = "parallel")]
+    pub fn par_apply_keystream(&mut self, data: &mut [u8]) -> Result<(), Error>
+    where
+        C: Sync,
+    {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let block_size = self.cipher.block_size();
+        let end = self
+            .position
+            .checked_add(data.len() as u64)
+            .ok_or(ModeError::CounterOverflow)?;
+        self.counter((end - 1) / block_size as u64)?;
+
+        // bring the position up to a block boundary so every task starts on a whole block
+        let unaligned = (block_size - (self.position % block_size as u64) as usize) % block_size;
+        let (head, tail) = data.split_at_mut(unaligned.min(data.len()));
+        self.apply_keystream(head)?;
+
+        let first_index = self.position / block_size as u64;
+        let key_stream = &*self;
+        tail.par_chunks_mut(block_size * BLOCKS_PER_TASK)
+            .enumerate()
+            .for_each(|(task, chunk)| {
+                let task_index = first_index + (task * BLOCKS_PER_TASK) as u64;
+                chunk
+                    .chunks_mut(block_size)
+                    .enumerate()
+                    .for_each(|(i, block)| {
+                        let key_stream_block = key_stream
+                            .key_stream_block(task_index + i as u64)
+                            .expect("the counter range has already been checked");
+                        block
+                            .iter_mut()
+                            .zip(key_stream_block)
+                            .for_each(|(x, y)| *x ^= y);
+                    });
+            });
+        self.position = end;
+
+        Ok(())
+    }
+
+    /// Returns the block of key stream for the given block index, independent of the position
+    ///
+    /// Errors
+    /// If the counter for that block would overflow
+    pub fn key_stream_block(&self, index: u64) -> Result<Vec<u8>, Error> {
+        let counter = self.counter(index)?;
+        let block_size = self.cipher.block_size();
+        // a counter wider than 128 bits is zero extended
+        let counter_length = (block_size - self.nonce.len()).min(16);
+        let mut block = self.nonce.clone();
+        match self.endianness {
+            Endianness::Big => {
+                block.resize(block_size - counter_length, 0);
+                block.extend_from_slice(&counter.to_be_bytes()[16 - counter_length..]);
+            }
+            Endianness::Little => {
+                block.extend_from_slice(&counter.to_le_bytes()[..counter_length]);
+                block.resize(block_size, 0);
+            }
+        }
+
+        self.cipher.encrypt_block(&mut block);
+        Ok(block)
+    }
+
+    /// The counter value for the given block index
+    fn counter(&self, index: u64) -> Result<u128, Error> {
+        let max_counter = self.max_counter();
+        if self.wrapping {
+            return Ok(self.initial_counter.wrapping_add(index as u128) & max_counter);
+        }
+
+        match self.initial_counter.checked_add(index as u128) {
+            Some(counter) if counter <= max_counter => Ok(counter),
+            _ => Err(Box::new(ModeError::CounterOverflow)),
+        }
+    }
+
+    fn max_counter(&self) -> u128 {
+        let counter_bits = (self.cipher.block_size() - self.nonce.len()) * 8;
+        if counter_bits >= 128 {
+            u128::MAX
+        } else {
+            (1 << counter_bits) - 1
+        }
+    }
+}
+
+/// Applies the counter mode key stream to everything read from or written to the inner stream
+///
+/// Wrapping a reader decrypts (or encrypts) as data is read; wrapping a writer encrypts (or
+/// decrypts) as data is written. Only one block of key stream is held at a time
+pub struct CtrStream<C: BlockCipher, S> {
+    key_stream: CtrKeystream<C>,
+    inner: S,
+}
+
+impl<C: BlockCipher, S> CtrStream<C, S> {
+    /// Uses the same counter block layout as [Ctr]
+    pub fn new(cipher: C, nonce: u64, inner: S) -> CtrStream<C, S> {
+        let nonce_length = cipher.block_size() / 2;
+        CtrStream::with_keystream(
+            CtrKeystream::with_layout(cipher, nonce, nonce_length),
+            inner,
+        )
+    }
+
+    /// Uses the given key stream from its current position
+    pub fn with_keystream(key_stream: CtrKeystream<C>, inner: S) -> CtrStream<C, S> {
+        CtrStream { key_stream, inner }
+    }
+
+    /// The number of bytes of key stream used so far
+    pub fn position(&self) -> u64 {
+        self.key_stream.position()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn apply(&mut self, data: &mut [u8]) -> io::Result<()> {
+        self.key_stream
+            .apply_keystream(data)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e.to_string()))
+    }
+}
+
+impl<C: BlockCipher, R: Read> Read for CtrStream<C, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.apply(&mut buf[..read])?;
+        Ok(read)
+    }
+}
+
+impl<C: BlockCipher, W: Write> Write for CtrStream<C, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut output = buf.to_vec();
+        let position = self.key_stream.position();
+        self.apply(&mut output)?;
+        // only the key stream for the bytes actually written is used up
+        let written = self.inner.write(&output);
+        self.key_stream
+            .seek(position + *written.as_ref().unwrap_or(&0) as u64);
+        written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::str::FromStr;
+
+    use crate::cyphers::aes::cipher::Aes;
+    use crate::cyphers::block_cipher::BlockCipher;
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::ctr::{Ctr, CtrKeystream, CtrStream, Endianness};
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        Hex::from_str(hex).unwrap().bytes().to_vec()
+    }
+
+    /// A cipher with a 32 byte block that leaves the block as it is, so the key stream is the
+    /// counter block itself
+    struct WideIdentity;
+
+    impl BlockCipher for WideIdentity {
+        fn block_size(&self) -> usize {
+            32
+        }
+
+        fn encrypt_block(&self, _block: &mut [u8]) {}
+
+        fn decrypt_block(&self, _block: &mut [u8]) {}
+    }
+
+    #[test]
+    fn counters_wider_than_128_bits_are_zero_extended() {
+        let nonce = [0xaa; 4];
+        let big = CtrKeystream::new(WideIdentity, &nonce, Endianness::Big).unwrap();
+        let block = big.key_stream_block(0x0102).unwrap();
+        assert_eq!(nonce, block[..4]);
+        assert!(block[4..30].iter().all(|byte| *byte == 0));
+        assert_eq!([1, 2], block[30..]);
+
+        let little = CtrKeystream::new(WideIdentity, &nonce, Endianness::Little).unwrap();
+        let block = little.key_stream_block(0x0102).unwrap();
+        assert_eq!(nonce, block[..4]);
+        assert_eq!([2, 1], block[4..6]);
+        assert!(block[6..].iter().all(|byte| *byte == 0));
+        assert_eq!(32, block.len());
+    }
+
+    #[test]
+    fn round_trip_with_eight_byte_blocks() {
+        let ctr = Ctr::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let plain_text = "CTR does not need any padding".as_bytes();
+
+        let encrypted = ctr.apply_keystream(plain_text, 42);
+        assert_eq!(plain_text.len(), encrypted.len());
+
+        assert_eq!(plain_text, ctr.apply_keystream(&encrypted, 42));
+    }
+
+    #[test]
+    fn nonce_changes_key_stream() {
+        let ctr = Ctr::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+
+        assert_ne!(ctr.key_stream_block(0, 0), ctr.key_stream_block(1, 0));
+        assert_ne!(ctr.key_stream_block(0, 0), ctr.key_stream_block(0, 1));
+    }
+
+    #[test]
+    fn stream_matches_slice_encryption() {
+        let cipher = Feistel::new("YELLOW SUBMARINE".as_bytes());
+        let plain_text = "read through the stream a few bytes at a time".as_bytes();
+        let encrypted = Ctr::new(&cipher).apply_keystream(plain_text, 7);
+
+        let mut stream = CtrStream::new(&cipher, 7, encrypted.as_slice());
+        let mut decrypted = Vec::new();
+        let mut buf = [0; 3];
+        loop {
+            let read = stream.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            decrypted.extend_from_slice(&buf[..read]);
+        }
+
+        assert_eq!(plain_text, decrypted);
+        assert_eq!(plain_text.len() as u64, stream.position());
+    }
+
+    /// F.5.1 of NIST SP 800-38A, read as a 96 bit nonce and a 32 bit counter
+    #[test]
+    fn matches_nist_vectors() {
+        let cipher = Aes::new(&bytes("2b7e151628aed2a6abf7158809cf4f3c")).unwrap();
+        let plain_text = bytes("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710");
+        let cipher_text = bytes("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee");
+
+        let mut key_stream =
+            CtrKeystream::new(&cipher, &bytes("f0f1f2f3f4f5f6f7f8f9fafb"), Endianness::Big)
+                .unwrap()
+                .with_initial_counter(0xfcfdfeff)
+                .unwrap();
+        let mut data = plain_text.clone();
+        key_stream.apply_keystream(&mut data).unwrap();
+        assert_eq!(cipher_text, data);
+
+        // the same counter blocks split as a 64 bit nonce and 64 bit counter
+        let mut key_stream =
+            CtrKeystream::new(&cipher, &bytes("f0f1f2f3f4f5f6f7"), Endianness::Big)
+                .unwrap()
+                .with_initial_counter(0xf8f9fafbfcfdfeff)
+                .unwrap();
+        key_stream.apply_keystream(&mut data).unwrap();
+        assert_eq!(plain_text, data);
+    }
+
+    /// Test vector #2 of RFC 3686
+    #[test]
+    fn matches_rfc3686_vectors() {
+        let cipher = Aes::new(&bytes("7e24067817fae0d743d6ce1f32539163")).unwrap();
+        let mut key_stream = CtrKeystream::rfc3686(
+            cipher,
+            [0x00, 0x6c, 0xb6, 0xdb],
+            [0xc0, 0x54, 0x3b, 0x59, 0xda, 0x48, 0xd9, 0x0b],
+        )
+        .unwrap();
+
+        let mut data = bytes("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+        key_stream.apply_keystream(&mut data).unwrap();
+        assert_eq!(
+            bytes("5104a106168a72d9790d41ee8edad388eb2e1efc46da57c8fce630df9141be28"),
+            data
+        );
+
+        let feistel = Feistel::new("YELLOW SUBMARINE".as_bytes());
+        let error = CtrKeystream::rfc3686(feistel, [0; 4], [0; 8])
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("16 byte block"), "{error}");
+    }
+
+    #[test]
+    fn seeking_matches_sequential_key_stream() {
+        let ctr = Ctr::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let encrypted = ctr.apply_keystream(&[0; 40], 9);
+
+        let mut key_stream = ctr.keystream(9);
+        key_stream.seek(13);
+        let mut data = [0; 20];
+        key_stream.apply_keystream(&mut data).unwrap();
+
+        assert_eq!(encrypted[13..33], data);
+        assert_eq!(33, key_stream.position());
+    }
+
+    #[test]
+    fn counter_overflow_is_detected() {
+        let cipher = Aes::new(&[1; 16]).unwrap();
+        let mut key_stream = CtrKeystream::new(&cipher, &[0; 15], Endianness::Little)
+            .unwrap()
+            .with_initial_counter(0xfe)
+            .unwrap();
+
+        let mut data = [0; 33];
+        assert!(key_stream.apply_keystream(&mut data).is_err());
+        assert_eq!([0; 33], data);
+        assert!(key_stream.apply_keystream(&mut data[..32]).is_ok());
+
+        assert!(CtrKeystream::new(&cipher, &[0; 15], Endianness::Big)
+            .unwrap()
+            .with_initial_counter(0x100)
+            .is_err());
+        assert!(CtrKeystream::new(&cipher, &[0; 16], Endianness::Big).is_err());
+
+        let mut wrapping = CtrKeystream::new(&cipher, &[0; 15], Endianness::Little)
+            .unwrap()
+            .with_initial_counter(0xff)
+            .unwrap()
+            .wrapping();
+        assert!(wrapping.apply_keystream(&mut data).is_ok());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_matches_sequential_key_stream() {
+        let ctr = Ctr::new(Aes::new(&[8; 16]).unwrap());
+        let plain_text: Vec<u8> = (0..100_000).map(|i| i as u8).collect();
+
+        assert_eq!(
+            ctr.apply_keystream(&plain_text, 5),
+            ctr.par_apply_keystream(&plain_text, 5)
+        );
+
+        // starting part way through a block
+        let mut sequential = ctr.keystream(5);
+        sequential.seek(7);
+        let mut expected = plain_text.clone();
+        sequential.apply_keystream(&mut expected).unwrap();
+
+        let mut parallel = ctr.keystream(5);
+        parallel.seek(7);
+        let mut data = plain_text.clone();
+        parallel.par_apply_keystream(&mut data).unwrap();
+
+        assert_eq!(expected, data);
+        assert_eq!(sequential.position(), parallel.position());
+    }
+}
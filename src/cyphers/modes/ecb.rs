@@ -0,0 +1,88 @@
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::modes::ModeError;
+use crate::cyphers::padding::pkcs7::Pkcs7;
+use crate::cyphers::padding::Padding;
+use crate::Error;
+
+/// Electronic codebook mode: every block is encrypted independently with the same key
+///
+/// Plain text is padded with pkcs#7 unless another padding scheme is chosen
+pub struct Ecb<C: BlockCipher, P: Padding = Pkcs7> {
+    cipher: C,
+    padding: P,
+}
+
+impl<C: BlockCipher> Ecb<C> {
+    pub fn new(cipher: C) -> Ecb<C> {
+        Ecb::with_padding(cipher, Pkcs7)
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Ecb<C, P> {
+    pub fn with_padding(cipher: C, padding: P) -> Ecb<C, P> {
+        Ecb { cipher, padding }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Pads the plain text and encrypts it block by block
+    pub fn encrypt(&self, plain_text: &[u8]) -> Vec<u8> {
+        let block_size = self.cipher.block_size();
+        let mut cipher_text = self.padding.pad(plain_text, block_size);
+        cipher_text
+            .chunks_mut(block_size)
+            .for_each(|block| self.cipher.encrypt_block(block));
+        cipher_text
+    }
+
+    /// Decrypts the cipher text block by block and removes the padding
+    ///
+    /// Errors
+    /// If the cipher text is empty or not a whole number of blocks
+    /// If the decrypted plain text does not end with valid padding
+    pub fn try_decrypt(&self, cipher_text: &[u8]) -> Result<Vec<u8>, Error> {
+        let block_size = self.cipher.block_size();
+        if cipher_text.is_empty() || !cipher_text.len().is_multiple_of(block_size) {
+            return Err(Box::new(ModeError::InvalidCipherTextLength {
+                length: cipher_text.len(),
+                block_size,
+            }));
+        }
+
+        let mut plain_text = cipher_text.to_vec();
+        plain_text
+            .chunks_mut(block_size)
+            .for_each(|block| self.cipher.decrypt_block(block));
+
+        Ok(self.padding.unpad(&plain_text, block_size)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::ecb::Ecb;
+    use crate::encoding::Digest;
+
+    #[test]
+    fn round_trip_with_eight_byte_blocks() {
+        let ecb = Ecb::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let plain_text = "eight byte blocks".as_bytes();
+
+        let encrypted = ecb.encrypt(plain_text);
+        assert_eq!(24, encrypted.len());
+
+        assert_eq!(plain_text, ecb.try_decrypt(&encrypted).unwrap());
+    }
+
+    #[test]
+    fn identical_blocks_are_visible() {
+        let ecb = Ecb::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+
+        let encrypted = ecb.encrypt("A".repeat(16).as_bytes());
+
+        assert!(encrypted.duplicate_blocks(8));
+    }
+}
@@ -0,0 +1,61 @@
+use crate::cyphers::block_cipher::BlockCipher;
+
+/// Output feedback mode: the iv is repeatedly encrypted to produce a key stream that is
+/// independent of both plain and cipher text
+///
+/// Encryption and decryption are the same operation and no padding is needed
+pub struct Ofb<C: BlockCipher> {
+    cipher: C,
+}
+
+impl<C: BlockCipher> Ofb<C> {
+    pub fn new(cipher: C) -> Ofb<C> {
+        Ofb { cipher }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Xors the data with the key stream generated from the iv
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn apply_keystream(&self, data: &[u8], iv: &[u8]) -> Vec<u8> {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+
+        let mut key_stream = iv.to_vec();
+        data.chunks(block_size)
+            .flat_map(|chunk| {
+                self.cipher.encrypt_block(&mut key_stream);
+                chunk
+                    .iter()
+                    .zip(&key_stream)
+                    .map(|(x, y)| x ^ y)
+                    .collect::<Vec<u8>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::ofb::Ofb;
+
+    #[test]
+    fn bit_flip_only_changes_one_bit() {
+        let ofb = Ofb::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let iv = [2; 8];
+        let plain_text = "ofb errors do not propagate at all".as_bytes();
+
+        let mut encrypted = ofb.apply_keystream(plain_text, &iv);
+        assert_eq!(plain_text.len(), encrypted.len());
+        encrypted[3] ^= 1;
+
+        let mut expected = plain_text.to_vec();
+        expected[3] ^= 1;
+        assert_eq!(expected, ofb.apply_keystream(&encrypted, &iv));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::cyphers::block_cipher::BlockCipher;
+use crate::cyphers::modes::ModeError;
+use crate::cyphers::padding::pkcs7::Pkcs7;
+use crate::cyphers::padding::Padding;
+use crate::Error;
+
+/// Propagating cipher block chaining mode: each plain text block is xor'd with both the previous
+/// plain text block and the previous cipher text block (or the iv for the first block) before it
+/// is encrypted, so an error in any cipher text block garbles every block after it
+///
+/// Plain text is padded with pkcs#7 unless another padding scheme is chosen
+pub struct Pcbc<C: BlockCipher, P: Padding = Pkcs7> {
+    cipher: C,
+    padding: P,
+}
+
+impl<C: BlockCipher> Pcbc<C> {
+    pub fn new(cipher: C) -> Pcbc<C> {
+        Pcbc::with_padding(cipher, Pkcs7)
+    }
+}
+
+impl<C: BlockCipher, P: Padding> Pcbc<C, P> {
+    pub fn with_padding(cipher: C, padding: P) -> Pcbc<C, P> {
+        Pcbc { cipher, padding }
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Pads the plain text and encrypts it
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn encrypt(&self, plain_text: &[u8], iv: &[u8]) -> Vec<u8> {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+
+        let mut cipher_text = self.padding.pad(plain_text, block_size);
+        let mut chain = iv.to_vec();
+        cipher_text.chunks_mut(block_size).for_each(|block| {
+            let plain_block = block.to_vec();
+            block.iter_mut().zip(&chain).for_each(|(x, y)| *x ^= y);
+            self.cipher.encrypt_block(block);
+            chain
+                .iter_mut()
+                .zip(plain_block.iter().zip(block.iter()))
+                .for_each(|(x, (p, c))| *x = p ^ c);
+        });
+
+        cipher_text
+    }
+
+    /// Decrypts the cipher text; padding is left in place for the caller to validate
+    ///
+    /// Errors
+    /// If the cipher text is not a whole number of blocks
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn decrypt(&self, cipher_text: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+        let block_size = self.cipher.block_size();
+        assert_eq!(block_size, iv.len(), "iv must be one block long");
+        if !cipher_text.len().is_multiple_of(block_size) {
+            return Err(Box::new(ModeError::InvalidCipherTextLength {
+                length: cipher_text.len(),
+                block_size,
+            }));
+        }
+
+        let mut chain = iv.to_vec();
+        let plain_text = cipher_text
+            .chunks(block_size)
+            .flat_map(|chunk| {
+                let mut block = chunk.to_vec();
+                self.cipher.decrypt_block(&mut block);
+                block.iter_mut().zip(&chain).for_each(|(x, y)| *x ^= y);
+                chain
+                    .iter_mut()
+                    .zip(block.iter().zip(chunk))
+                    .for_each(|(x, (p, c))| *x = p ^ c);
+                block
+            })
+            .collect();
+        Ok(plain_text)
+    }
+
+    /// Decrypts the cipher text and removes the padding
+    ///
+    /// Errors
+    /// If the cipher text is not a whole number of blocks
+    /// If the decrypted plain text does not end with valid padding
+    ///
+    /// Panics
+    /// If the iv is not the same length as the cipher's block size
+    pub fn try_decrypt(&self, cipher_text: &[u8], iv: &[u8]) -> Result<Vec<u8>, Error> {
+        let plain_text = self.decrypt(cipher_text, iv)?;
+        Ok(self.padding.unpad(&plain_text, self.cipher.block_size())?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::feistel::Feistel;
+    use crate::cyphers::modes::pcbc::Pcbc;
+    use crate::cyphers::padding::ansi_x923::AnsiX923;
+
+    #[test]
+    fn corrupted_block_garbles_the_rest() {
+        let pcbc = Pcbc::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let iv = [3; 8];
+        let plain_text = "A".repeat(32);
+
+        let mut encrypted = pcbc.encrypt(plain_text.as_bytes(), &iv);
+        assert_eq!(
+            plain_text.as_bytes(),
+            pcbc.try_decrypt(&encrypted, &iv).unwrap()
+        );
+
+        encrypted[9] ^= 1;
+        let decrypted = pcbc.decrypt(&encrypted, &iv).unwrap();
+        assert_eq!(plain_text.as_bytes()[..8], decrypted[..8]);
+        decrypted[8..32]
+            .chunks(8)
+            .for_each(|block| assert_ne!(plain_text.as_bytes()[..8], *block));
+    }
+
+    #[test]
+    fn chosen_padding_is_applied() {
+        let pcbc = Pcbc::with_padding(Feistel::new("YELLOW SUBMARINE".as_bytes()), AnsiX923);
+        let iv = [3; 8];
+
+        let encrypted = pcbc.encrypt("ten bytes!".as_bytes(), &iv);
+        assert_eq!(
+            [0, 0, 0, 0, 0, 6],
+            pcbc.decrypt(&encrypted, &iv).unwrap()[10..]
+        );
+        assert_eq!(
+            "ten bytes!".as_bytes(),
+            pcbc.try_decrypt(&encrypted, &iv).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_cipher_text_is_rejected() {
+        let pcbc = Pcbc::new(Feistel::new("YELLOW SUBMARINE".as_bytes()));
+        let iv = [3; 8];
+        let mut encrypted = pcbc.encrypt("some message".as_bytes(), &iv);
+        encrypted.pop();
+
+        assert!(pcbc.decrypt(&encrypted, &iv).is_err());
+        assert!(pcbc.try_decrypt(&encrypted, &iv).is_err());
+    }
+}
@@ -1,17 +1,94 @@
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+pub mod ansi_x923;
+pub mod iso10126;
+pub mod iso7816;
 pub mod pkcs7;
+pub mod zero;
 
-#[derive(Debug, PartialEq)]
-enum PaddingError {
-    InvalidPadding(String),
+/// The reason a padded plain text was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingError {
+    /// There was nothing to unpad
+    Empty,
+    /// The padding length given by the final byte is zero, longer than a block or longer than the
+    /// plain text
+    InvalidLength { length: usize, block_size: usize },
+    /// A padding byte had the wrong value; the position counts back from the end of the plain
+    /// text, so the last byte is at position 0
+    UnexpectedByte {
+        position: usize,
+        expected: u8,
+        found: u8,
+    },
+    /// The final block had no 0x80 marker byte before the trailing zeros
+    MissingMarker,
 }
 
 impl Display for PaddingError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            PaddingError::Empty => write!(f, "cannot unpad an empty plain text"),
+            PaddingError::InvalidLength { length, block_size } => write!(
+                f,
+                "padding length {length} is not valid for a {block_size} byte block"
+            ),
+            PaddingError::UnexpectedByte {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected padding byte {expected:#04x} {position} bytes from the end, found {found:#04x}"
+            ),
+            PaddingError::MissingMarker => write!(f, "no 0x80 padding marker found"),
+        }
     }
 }
 
 impl Error for PaddingError {}
+
+/// A scheme for extending plain text to a whole number of blocks
+pub trait Padding {
+    /// Pads the plain text to a whole number of blocks
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8>;
+
+    /// Validates and removes the padding from a padded plain text
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError>;
+}
+
+impl<P: Padding + ?Sized> Padding for &P {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        (**self).pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        (**self).unpad(plain_text, block_size)
+    }
+}
+
+impl<P: Padding + ?Sized> Padding for Box<P> {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        (**self).pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        (**self).unpad(plain_text, block_size)
+    }
+}
+
+/// Checks the final byte gives a padding length between one and a block, and no longer than the
+/// plain text; shared by the schemes that end with a length byte
+fn final_length_byte(plain_text: &[u8], block_size: usize) -> Result<usize, PaddingError> {
+    let length = *plain_text.last().ok_or(PaddingError::Empty)? as usize;
+    if length == 0 || length > block_size || length > plain_text.len() {
+        return Err(PaddingError::InvalidLength { length, block_size });
+    }
+    Ok(length)
+}
+
+/// The number of bytes needed to pad to the next whole block; always at least one
+fn padding_length(length: usize, block_size: usize) -> usize {
+    block_size - (length % block_size)
+}
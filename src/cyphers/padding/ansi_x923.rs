@@ -0,0 +1,71 @@
+use crate::cyphers::padding::{final_length_byte, padding_length, Padding, PaddingError};
+
+/// ANSI X9.23: zero bytes followed by a final byte holding the number of padding bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnsiX923;
+
+impl Padding for AnsiX923 {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        try_unpad(plain_text, block_size)
+    }
+}
+
+/// Pads the plain text to a whole number of blocks using ANSI X9.23
+pub fn pad(plain_text: &[u8], block_size: usize) -> Vec<u8> {
+    let padding = padding_length(plain_text.len(), block_size);
+    let mut padded = plain_text.to_vec();
+    padded.resize(plain_text.len() + padding - 1, 0);
+    padded.push(padding as u8);
+    padded
+}
+
+/// Validates and removes ANSI X9.23 padding
+///
+/// Errors
+/// If the plain text is empty, the length byte is invalid or any other padding byte is not zero
+pub fn try_unpad(plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let padding = final_length_byte(plain_text, block_size)?;
+
+    for (position, byte) in plain_text.iter().rev().enumerate().take(padding).skip(1) {
+        if *byte != 0 {
+            return Err(PaddingError::UnexpectedByte {
+                position,
+                expected: 0,
+                found: *byte,
+            });
+        }
+    }
+
+    Ok(plain_text[..plain_text.len() - padding].to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::padding::ansi_x923::{pad, try_unpad};
+    use crate::cyphers::padding::PaddingError;
+
+    #[test]
+    fn round_trip() {
+        let padded = pad("ICE ICE BABY".as_bytes(), 16);
+        assert_eq!("ICE ICE BABY\x00\x00\x00\x04".as_bytes(), padded);
+        assert_eq!("ICE ICE BABY".as_bytes(), try_unpad(&padded, 16).unwrap());
+
+        assert_eq!(vec![8; 1], pad(&[], 8)[7..]);
+    }
+
+    #[test]
+    fn non_zero_padding_is_rejected() {
+        assert_eq!(
+            Err(PaddingError::UnexpectedByte {
+                position: 2,
+                expected: 0,
+                found: 1
+            }),
+            try_unpad("ICE ICE BABY\x00\x01\x00\x04".as_bytes(), 16)
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use rand::RngCore;
+
+use crate::cyphers::padding::{final_length_byte, padding_length, Padding, PaddingError};
+
+/// ISO 10126: random bytes followed by a final byte holding the number of padding bytes
+///
+/// Only the final byte can be validated, which makes a padding oracle far less informative
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Iso10126;
+
+impl Padding for Iso10126 {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        try_unpad(plain_text, block_size)
+    }
+}
+
+/// Pads the plain text to a whole number of blocks using ISO 10126
+pub fn pad(plain_text: &[u8], block_size: usize) -> Vec<u8> {
+    let padding = padding_length(plain_text.len(), block_size);
+    let mut random = vec![0; padding - 1];
+    rand::thread_rng().fill_bytes(&mut random);
+
+    [plain_text, &random, &[padding as u8]].concat()
+}
+
+/// Removes ISO 10126 padding
+///
+/// Errors
+/// If the plain text is empty or the length byte is invalid
+pub fn try_unpad(plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    let padding = final_length_byte(plain_text, block_size)?;
+    Ok(plain_text[..plain_text.len() - padding].to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::padding::iso10126::{pad, try_unpad};
+
+    #[test]
+    fn round_trip() {
+        let padded = pad("ICE ICE BABY".as_bytes(), 16);
+        assert_eq!(16, padded.len());
+        assert_eq!(4, padded[15]);
+        assert_eq!("ICE ICE BABY".as_bytes(), try_unpad(&padded, 16).unwrap());
+
+        assert!(try_unpad("ICE ICE BABY\x00\x00\x00\x11".as_bytes(), 16).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::cyphers::padding::{padding_length, Padding, PaddingError};
+
+const MARKER: u8 = 0x80;
+
+/// ISO/IEC 7816-4: a single 0x80 byte followed by as many zero bytes as needed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Iso7816;
+
+impl Padding for Iso7816 {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        try_unpad(plain_text, block_size)
+    }
+}
+
+/// Pads the plain text to a whole number of blocks using ISO/IEC 7816-4
+pub fn pad(plain_text: &[u8], block_size: usize) -> Vec<u8> {
+    let padding = padding_length(plain_text.len(), block_size);
+    let mut padded = plain_text.to_vec();
+    padded.push(MARKER);
+    padded.resize(plain_text.len() + padding, 0);
+    padded
+}
+
+/// Validates and removes ISO/IEC 7816-4 padding
+///
+/// Errors
+/// If the plain text is empty, the last non-zero byte is not 0x80 or there are a block or more
+/// of trailing zeros
+pub fn try_unpad(plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+    if plain_text.is_empty() {
+        return Err(PaddingError::Empty);
+    }
+
+    for (position, byte) in plain_text.iter().rev().enumerate().take(block_size) {
+        match *byte {
+            0 => continue,
+            MARKER => return Ok(plain_text[..plain_text.len() - position - 1].to_vec()),
+            found => {
+                return Err(PaddingError::UnexpectedByte {
+                    position,
+                    expected: MARKER,
+                    found,
+                })
+            }
+        }
+    }
+
+    Err(PaddingError::MissingMarker)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::padding::iso7816::{pad, try_unpad};
+    use crate::cyphers::padding::PaddingError;
+
+    #[test]
+    fn round_trip() {
+        let padded = pad("ICE ICE BABY".as_bytes(), 16);
+        assert_eq!([&b"ICE ICE BABY"[..], &[0x80, 0, 0, 0]].concat(), padded);
+        assert_eq!("ICE ICE BABY".as_bytes(), try_unpad(&padded, 16).unwrap());
+
+        assert_eq!(vec![0x80], pad(&[1; 7], 8)[7..]);
+    }
+
+    #[test]
+    fn missing_marker_is_rejected() {
+        assert_eq!(Err(PaddingError::MissingMarker), try_unpad(&[0; 8], 8));
+        assert_eq!(
+            Err(PaddingError::UnexpectedByte {
+                position: 1,
+                expected: 0x80,
+                found: 4
+            }),
+            try_unpad(&[1, 2, 3, 4, 0], 8)
+        );
+    }
+}
@@ -1,10 +1,23 @@
-use crate::cyphers::padding::PaddingError;
-use crate::Error;
+use crate::cyphers::padding::{final_length_byte, padding_length, Padding, PaddingError};
+
+/// PKCS#7: every padding byte holds the number of padding bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pkcs7;
+
+impl Padding for Pkcs7 {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        try_unpad(plain_text, block_size)
+    }
+}
 
 /// pads a given plain text to the target length using the pkcs#7 standard
 pub fn pad(plain_text: &[u8], target_length: usize) -> Vec<u8> {
     let mut owned = plain_text.to_vec();
-    let padding = target_length - (owned.len() % target_length);
+    let padding = padding_length(owned.len(), target_length);
 
     (0..padding).for_each(|_| owned.push(padding as u8));
 
@@ -12,43 +25,28 @@ pub fn pad(plain_text: &[u8], target_length: usize) -> Vec<u8> {
 }
 
 /// validates and unpads a given plain text that has been padded using pkcs#7
-/// returns Error if the padding is not valid
-///
-/// Panics
-/// If the plain_text slice is empty
-pub fn try_unpad(plain_text: &[u8], block_length: usize) -> Result<Vec<u8>, Error> {
-    let last_byte = *plain_text.last().unwrap();
-
-    if last_byte as usize > block_length {
-        return Err(Box::new(PaddingError::InvalidPadding(
-            "last byte greater than maximum padding".to_string(),
-        )));
-    }
-
-    if last_byte == 0 {
-        return Err(Box::new(PaddingError::InvalidPadding(
-            "last byte 0".to_string(),
-        )));
-    }
-
-    let mut result = plain_text.to_vec();
-    for _ in (plain_text.len() - last_byte as usize)..plain_text.len() {
-        let byte = *result.last().unwrap();
-        if byte != last_byte {
-            return Err(Box::new(PaddingError::InvalidPadding(format!(
-                "expected {last_byte} found {byte}"
-            ))));
-        } else {
-            result.pop();
+/// returns Error if the padding is not valid or the plain text is empty
+pub fn try_unpad(plain_text: &[u8], block_length: usize) -> Result<Vec<u8>, PaddingError> {
+    let padding = final_length_byte(plain_text, block_length)?;
+    let last_byte = padding as u8;
+
+    for (position, byte) in plain_text.iter().rev().enumerate().take(padding) {
+        if *byte != last_byte {
+            return Err(PaddingError::UnexpectedByte {
+                position,
+                expected: last_byte,
+                found: *byte,
+            });
         }
     }
 
-    Ok(result)
+    Ok(plain_text[..plain_text.len() - padding].to_vec())
 }
 
 #[cfg(test)]
 mod test {
     use crate::cyphers::padding::pkcs7::{pad, try_unpad};
+    use crate::cyphers::padding::PaddingError;
 
     #[test]
     fn padding_appends_bytes() {
@@ -112,5 +110,8 @@ mod test {
         let plain_text = "ICE ICE BABY\x01\x02\x04\x7f";
         let result = try_unpad(plain_text.as_bytes(), block_length);
         assert!(result.is_err());
+
+        assert_eq!(Err(PaddingError::Empty), try_unpad(&[], block_length));
+        assert!(try_unpad(&[3, 3], block_length).is_err());
     }
 }
@@ -0,0 +1,52 @@
+use crate::cyphers::padding::{Padding, PaddingError};
+
+/// Zero padding: zero bytes up to the end of the block, and nothing at all if the plain text is
+/// already a whole number of blocks
+///
+/// Unpadding strips every trailing zero in the final block, so plain text that ends in zero
+/// bytes does not survive a round trip; there is nothing to validate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZeroPadding;
+
+impl Padding for ZeroPadding {
+    fn pad(&self, plain_text: &[u8], block_size: usize) -> Vec<u8> {
+        pad(plain_text, block_size)
+    }
+
+    fn unpad(&self, plain_text: &[u8], block_size: usize) -> Result<Vec<u8>, PaddingError> {
+        Ok(unpad(plain_text, block_size))
+    }
+}
+
+/// Pads the plain text with zeros to a whole number of blocks
+pub fn pad(plain_text: &[u8], block_size: usize) -> Vec<u8> {
+    let mut padded = plain_text.to_vec();
+    padded.resize(plain_text.len().next_multiple_of(block_size), 0);
+    padded
+}
+
+/// Removes the trailing zeros from the final block
+pub fn unpad(plain_text: &[u8], block_size: usize) -> Vec<u8> {
+    let zeros = plain_text
+        .iter()
+        .rev()
+        .take(block_size)
+        .take_while(|byte| **byte == 0)
+        .count();
+    plain_text[..plain_text.len() - zeros].to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cyphers::padding::zero::{pad, unpad};
+
+    #[test]
+    fn round_trip() {
+        let padded = pad("ICE ICE BABY".as_bytes(), 16);
+        assert_eq!("ICE ICE BABY\x00\x00\x00\x00".as_bytes(), padded);
+        assert_eq!("ICE ICE BABY".as_bytes(), unpad(&padded, 16));
+
+        // whole blocks are left alone
+        assert_eq!(vec![1; 16], pad(&[1; 16], 16));
+    }
+}
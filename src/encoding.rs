@@ -2,7 +2,11 @@ use std::collections::HashMap;
 
 use crate::OrderedFloat;
 
+pub mod ascii85;
+pub mod base32;
+pub mod base58;
 pub mod base64;
+pub mod detect;
 pub mod hex;
 pub mod structured_cookie;
 
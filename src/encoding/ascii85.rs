@@ -0,0 +1,226 @@
+//! Ascii85 as used by Adobe's PostScript and PDF `ASCII85Decode` filter
+//!
+//! Each group of 4 bytes is written as a 5 digit base 85 number using the characters `!` to `u`,
+//! except that a group of four zero bytes is written `z`. A final group of fewer than 4 bytes
+//! is padded with zeros and only as many digits written as are needed to recover it. Encoding
+//! writes the bare digits; decoding ignores whitespace and accepts the `<~` and `~>` delimiters
+//! that surround the digits in PostScript and end them in a PDF stream
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::encoding::base64::Base64;
+use crate::encoding::hex::Hex;
+use crate::encoding::Digest;
+use crate::Error;
+
+const FIRST_DIGIT: u8 = b'!';
+const ZERO_GROUP: u8 = b'z';
+
+#[derive(Debug, PartialEq)]
+enum Ascii85Error {
+    InvalidByte { byte: u8, offset: usize },
+    MisplacedZeroGroup { offset: usize },
+    GroupOverflow { offset: usize },
+    InvalidLength,
+}
+
+impl Display for Ascii85Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ascii85Error::InvalidByte { byte, offset } => {
+                write!(f, "invalid ascii85 byte {byte:#04x} at offset {offset}")
+            }
+            Ascii85Error::MisplacedZeroGroup { offset } => {
+                write!(f, "'z' inside a group at offset {offset}")
+            }
+            Ascii85Error::GroupOverflow { offset } => {
+                write!(
+                    f,
+                    "the group ending at offset {offset} doesn't fit in 4 bytes"
+                )
+            }
+            Ascii85Error::InvalidLength => {
+                write!(f, "a single ascii85 digit can't encode a whole byte")
+            }
+        }
+    }
+}
+
+impl StdError for Ascii85Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ascii85 {
+    bytes: Vec<u8>,
+}
+
+impl Digest for Ascii85 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Digest for &Ascii85 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Display for Ascii85 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::encode(&self.bytes))
+    }
+}
+
+impl FromStr for Ascii85 {
+    type Err = Error;
+
+    /// Errors
+    /// If the string contains characters outside `!` to `u` other than `z` between groups, or a
+    /// group too large for 4 bytes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("<~").unwrap_or(s);
+        let s = s.strip_suffix("~>").unwrap_or(s);
+
+        let mut bytes = Vec::with_capacity(s.len() * 4 / 5);
+        let mut group = [0u8; 5];
+        let mut digits = 0;
+
+        for (offset, byte) in s.bytes().enumerate() {
+            match byte {
+                _ if byte.is_ascii_whitespace() => continue,
+                ZERO_GROUP if digits == 0 => bytes.extend_from_slice(&[0; 4]),
+                ZERO_GROUP => return Err(Box::new(Ascii85Error::MisplacedZeroGroup { offset })),
+                b'!'..=b'u' => {
+                    group[digits] = byte - FIRST_DIGIT;
+                    digits += 1;
+                    if digits == 5 {
+                        let value =
+                            decode_group(&group).ok_or(Ascii85Error::GroupOverflow { offset })?;
+                        bytes.extend_from_slice(&value.to_be_bytes());
+                        digits = 0;
+                    }
+                }
+                _ => return Err(Box::new(Ascii85Error::InvalidByte { byte, offset })),
+            }
+        }
+
+        match digits {
+            0 => {}
+            1 => return Err(Box::new(Ascii85Error::InvalidLength)),
+            _ => {
+                // padding with the largest digit rounds the value up into the right 4 bytes
+                group[digits..].fill(b'u' - FIRST_DIGIT);
+                let value =
+                    decode_group(&group).ok_or(Ascii85Error::GroupOverflow { offset: s.len() })?;
+                bytes.extend_from_slice(&value.to_be_bytes()[..digits - 1]);
+            }
+        }
+
+        Ok(Ascii85 { bytes })
+    }
+}
+
+impl From<Hex> for Ascii85 {
+    fn from(hex: Hex) -> Self {
+        Ascii85::new(hex.bytes())
+    }
+}
+
+impl From<Ascii85> for Hex {
+    fn from(ascii85: Ascii85) -> Self {
+        Hex::new(&ascii85.bytes)
+    }
+}
+
+impl From<Base64> for Ascii85 {
+    fn from(base64: Base64) -> Self {
+        Ascii85::new(base64.bytes())
+    }
+}
+
+impl From<Ascii85> for Base64 {
+    fn from(ascii85: Ascii85) -> Self {
+        Base64::new(&ascii85.bytes)
+    }
+}
+
+impl Ascii85 {
+    pub fn new(bytes: &[u8]) -> Ascii85 {
+        let bytes = bytes.to_vec();
+        Ascii85 { bytes }
+    }
+
+    /// Encodes the bytes without the `<~` and `~>` delimiters
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut result = String::with_capacity(bytes.len().div_ceil(4) * 5);
+
+        for chunk in bytes.chunks(4) {
+            if chunk == [0; 4] {
+                result.push(ZERO_GROUP as char);
+                continue;
+            }
+
+            let mut group = [0u8; 4];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let mut value = u32::from_be_bytes(group);
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = (value % 85) as u8 + FIRST_DIGIT;
+                value /= 85;
+            }
+            result.extend(digits[..=chunk.len()].iter().map(|digit| *digit as char));
+        }
+
+        result
+    }
+}
+
+/// The value of 5 base 85 digits, none if it doesn't fit in 4 bytes
+fn decode_group(group: &[u8; 5]) -> Option<u32> {
+    group.iter().try_fold(0u32, |value, digit| {
+        value.checked_mul(85)?.checked_add(*digit as u32)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::encoding::ascii85::Ascii85;
+    use crate::encoding::base64::Base64;
+    use crate::encoding::Digest;
+
+    #[test]
+    fn encodes_and_decodes() {
+        for (plain, encoded) in [
+            (&b""[..], ""),
+            (b"Man", "9jqo"),
+            (b"Man is distinguished", "9jqo^BlbD-BleB1DJ+*+F(f,q"),
+            (b"\0\0\0\0ab", "z@:B"),
+            (&[0xff; 4], "s8W-!"),
+        ] {
+            assert_eq!(encoded, Ascii85::new(plain).to_string());
+            assert_eq!(plain, Ascii85::from_str(encoded).unwrap().bytes());
+        }
+    }
+
+    #[test]
+    fn decodes_pdf_streams() {
+        let stream = Ascii85::from_str("<~9jqo^Blb\nD-BleB1DJ+*\r\n+F(f,q~>").unwrap();
+        assert_eq!(b"Man is distinguished", stream.bytes());
+        assert_eq!(b"Man", Ascii85::from_str("9jqo~>").unwrap().bytes());
+
+        for invalid in ["9jqoz", "9", "s8W-\"", "9jqo^v"] {
+            assert!(Ascii85::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_base64() {
+        let ascii85 = Ascii85::from(Base64::from_str("TWFu").unwrap());
+        assert_eq!("9jqo", ascii85.to_string());
+        assert_eq!("TWFu", Base64::from(ascii85).to_string());
+    }
+}
@@ -0,0 +1,221 @@
+//! Base32 as described in [RFC 4648](https://www.rfc-editor.org/rfc/rfc4648#section-6)
+//!
+//! Encodes with the standard upper case alphabet and padding. Decoding is forgiving of the ways
+//! TOTP secrets are usually written: lower case, grouped with spaces and without padding
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::encoding::base64::Base64;
+use crate::encoding::hex::Hex;
+use crate::encoding::Digest;
+use crate::Error;
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug, PartialEq)]
+enum Base32Error {
+    InvalidByte { byte: u8, offset: usize },
+    InvalidLength(usize),
+    MisplacedPadding,
+}
+
+impl Display for Base32Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base32Error::InvalidByte { byte, offset } => {
+                write!(f, "invalid base32 byte {byte:#04x} at offset {offset}")
+            }
+            Base32Error::InvalidLength(length) => {
+                write!(
+                    f,
+                    "{length} base32 symbols can't encode a whole number of bytes"
+                )
+            }
+            Base32Error::MisplacedPadding => {
+                write!(f, "padding doesn't fill the final group of 8 symbols")
+            }
+        }
+    }
+}
+
+impl StdError for Base32Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base32 {
+    bytes: Vec<u8>,
+}
+
+impl Digest for Base32 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Digest for &Base32 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Display for Base32 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::encode(&self.bytes))
+    }
+}
+
+impl FromStr for Base32 {
+    type Err = Error;
+
+    /// Letters may be upper or lower case, whitespace is ignored and padding is optional
+    ///
+    /// Errors
+    /// If the string contains symbols outside the alphabet, padding anywhere but the end or the
+    /// wrong amount of padding, or is a length that can't encode a whole number of bytes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes = Vec::with_capacity(s.len() * 5 / 8);
+        let mut buffer = 0u32;
+        let mut bits = 0;
+        let mut symbols = 0;
+        let mut padding = 0;
+
+        for (offset, byte) in s.bytes().enumerate() {
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+            if byte == b'=' {
+                padding += 1;
+                continue;
+            }
+            if padding > 0 {
+                return Err(Box::new(Base32Error::MisplacedPadding));
+            }
+
+            let value = ALPHABET
+                .iter()
+                .position(|symbol| *symbol == byte.to_ascii_uppercase())
+                .ok_or(Base32Error::InvalidByte { byte, offset })?;
+            buffer = (buffer << 5) | value as u32;
+            bits += 5;
+            symbols += 1;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+
+        // a final group of 8 symbols can only end after 2, 4, 5 or 7 of them
+        if matches!(symbols % 8, 1 | 3 | 6) {
+            return Err(Box::new(Base32Error::InvalidLength(symbols)));
+        }
+        if padding > 0 && (symbols + padding) % 8 != 0 {
+            return Err(Box::new(Base32Error::MisplacedPadding));
+        }
+
+        Ok(Base32 { bytes })
+    }
+}
+
+impl From<Hex> for Base32 {
+    fn from(hex: Hex) -> Self {
+        Base32::new(hex.bytes())
+    }
+}
+
+impl From<Base32> for Hex {
+    fn from(base32: Base32) -> Self {
+        Hex::new(&base32.bytes)
+    }
+}
+
+impl From<Base64> for Base32 {
+    fn from(base64: Base64) -> Self {
+        Base32::new(base64.bytes())
+    }
+}
+
+impl From<Base32> for Base64 {
+    fn from(base32: Base32) -> Self {
+        Base64::new(&base32.bytes)
+    }
+}
+
+impl Base32 {
+    pub fn new(bytes: &[u8]) -> Base32 {
+        let bytes = bytes.to_vec();
+        Base32 { bytes }
+    }
+
+    /// Encodes the bytes with the upper case alphabet, padded to a multiple of 8 symbols
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut result = String::with_capacity(bytes.len().div_ceil(5) * 8);
+        let mut buffer = 0u32;
+        let mut bits = 0;
+
+        for byte in bytes {
+            buffer = (buffer << 8) | *byte as u32;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                result.push(ALPHABET[(buffer >> bits) as usize & 0b1_1111] as char);
+            }
+        }
+        if bits > 0 {
+            result.push(ALPHABET[(buffer << (5 - bits)) as usize & 0b1_1111] as char);
+        }
+        while !result.len().is_multiple_of(8) {
+            result.push('=');
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::encoding::base32::Base32;
+    use crate::encoding::base64::Base64;
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    #[test]
+    fn rfc_4648_vectors() {
+        for (plain, encoded) in [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ] {
+            assert_eq!(encoded, Base32::new(plain.as_bytes()).to_string());
+            assert_eq!(plain.as_bytes(), Base32::from_str(encoded).unwrap().bytes());
+        }
+    }
+
+    #[test]
+    fn decodes_totp_secrets() {
+        let secret = Base32::from_str("jbsw y3dp ehpk 3pxp").unwrap();
+        assert_eq!(b"Hello!\xde\xad\xbe\xef", secret.bytes());
+        assert_eq!("JBSWY3DPEHPK3PXP", secret.to_string());
+
+        assert_eq!(b"f", Base32::from_str("my").unwrap().bytes());
+        for invalid in ["MY=", "M", "MY======MY======", "MZXW6YT1"] {
+            assert!(Base32::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_other_encodings() {
+        let base32 = Base32::from(Hex::from_str("deadbeef").unwrap());
+        assert_eq!("32W353Y=", base32.to_string());
+
+        let base64 = Base64::from(base32.clone());
+        assert_eq!("3q2+7w==", base64.to_string());
+        assert_eq!(base32, Base32::from(base64));
+        assert_eq!("deadbeef", Hex::from(base32).to_string());
+    }
+}
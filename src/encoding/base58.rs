@@ -0,0 +1,184 @@
+//! Base58 with the Bitcoin alphabet, which leaves out `0`, `O`, `I` and `l` so that identifiers
+//! can't be misread
+//!
+//! The bytes are treated as one big-endian number written in base 58, with each leading zero
+//! byte written as a `1`
+use std::error::Error as StdError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::encoding::base64::Base64;
+use crate::encoding::hex::Hex;
+use crate::encoding::Digest;
+use crate::Error;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Debug, PartialEq)]
+enum Base58Error {
+    InvalidByte { byte: u8, offset: usize },
+}
+
+impl Display for Base58Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base58Error::InvalidByte { byte, offset } => {
+                write!(f, "invalid base58 byte {byte:#04x} at offset {offset}")
+            }
+        }
+    }
+}
+
+impl StdError for Base58Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Base58 {
+    bytes: Vec<u8>,
+}
+
+impl Digest for Base58 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Digest for &Base58 {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Display for Base58 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::encode(&self.bytes))
+    }
+}
+
+impl FromStr for Base58 {
+    type Err = Error;
+
+    /// Errors
+    /// If the string contains symbols outside the alphabet
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let leading_zeros = s.bytes().take_while(|byte| *byte == ALPHABET[0]).count();
+
+        // little-endian base 256 digits of the number so far
+        let mut number: Vec<u8> = Vec::with_capacity(s.len() * 3 / 4);
+        for (offset, byte) in s.bytes().enumerate().skip(leading_zeros) {
+            let mut carry = ALPHABET
+                .iter()
+                .position(|symbol| *symbol == byte)
+                .ok_or(Base58Error::InvalidByte { byte, offset })?
+                as u32;
+            for digit in number.iter_mut() {
+                carry += *digit as u32 * 58;
+                *digit = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                number.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+
+        let bytes = std::iter::repeat_n(0, leading_zeros)
+            .chain(number.into_iter().rev())
+            .collect();
+        Ok(Base58 { bytes })
+    }
+}
+
+impl From<Hex> for Base58 {
+    fn from(hex: Hex) -> Self {
+        Base58::new(hex.bytes())
+    }
+}
+
+impl From<Base58> for Hex {
+    fn from(base58: Base58) -> Self {
+        Hex::new(&base58.bytes)
+    }
+}
+
+impl From<Base64> for Base58 {
+    fn from(base64: Base64) -> Self {
+        Base58::new(base64.bytes())
+    }
+}
+
+impl From<Base58> for Base64 {
+    fn from(base58: Base58) -> Self {
+        Base64::new(&base58.bytes)
+    }
+}
+
+impl Base58 {
+    pub fn new(bytes: &[u8]) -> Base58 {
+        let bytes = bytes.to_vec();
+        Base58 { bytes }
+    }
+
+    pub fn encode(bytes: &[u8]) -> String {
+        let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+
+        // little-endian base 58 digits of the number so far
+        let mut number: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+        for byte in &bytes[leading_zeros..] {
+            let mut carry = *byte as u32;
+            for digit in number.iter_mut() {
+                carry += (*digit as u32) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                number.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+
+        std::iter::repeat_n(ALPHABET[0], leading_zeros)
+            .chain(number.iter().rev().map(|digit| ALPHABET[*digit as usize]))
+            .map(char::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::encoding::base58::Base58;
+    use crate::encoding::hex::Hex;
+    use crate::encoding::Digest;
+
+    #[test]
+    fn encodes_and_decodes() {
+        for (plain, encoded) in [
+            (&b""[..], ""),
+            (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+            (
+                b"The quick brown fox jumps over the lazy dog.",
+                "USm3fpXnKG5EUBx2ndxBDMPVciP5hGey2Jh4NDv6gmeo1LkMeiKrLJUUBk6Z",
+            ),
+            (&[0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd], "11233QC4"),
+            (&[0x00], "1"),
+        ] {
+            assert_eq!(encoded, Base58::new(plain).to_string());
+            assert_eq!(plain, Base58::from_str(encoded).unwrap().bytes());
+        }
+    }
+
+    #[test]
+    fn rejects_ambiguous_symbols() {
+        for invalid in ["0", "O", "I", "l", "2NEpo7TZ+RRrLZSi2U"] {
+            assert!(Base58::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_hex() {
+        let base58 = Base58::from(Hex::from_str("0000287fb4cd").unwrap());
+        assert_eq!("11233QC4", base58.to_string());
+        assert_eq!("0000287fb4cd", Hex::from(base58).to_string());
+    }
+}
@@ -1,5 +1,14 @@
+//! Base64 as described in [RFC 4648](https://www.rfc-editor.org/rfc/rfc4648)
+//!
+//! [Base64] holds decoded bytes and encodes them with the standard alphabet and padding. Other
+//! variants are described by a [Config]: the URL and filename safe alphabet, unpadded output,
+//! MIME style line wrapping and lenient decoding that skips whitespace and accepts missing
+//! padding. [EncoderWriter] and [DecoderReader] encode and decode streams without holding the
+//! whole of either in memory
+use std::error::Error as StdError;
 use std::fmt::{Display, Formatter};
-use std::fs;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::str::FromStr;
 
 use crate::encoding::hex::Hex;
@@ -33,13 +42,7 @@ impl FromStr for Base64 {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bytes = s
-            .as_bytes()
-            .chunks(4)
-            .flat_map(Self::encoded_bytes_from_chunk)
-            .collect();
-
-        Ok(Base64 { bytes })
+        Base64::decode(s, &Config::STANDARD)
     }
 }
 
@@ -57,18 +60,14 @@ impl Base64 {
     /// Tries to create a Base64 from a file
     ///
     /// Assumptions:
-    /// The file contains only the base64 encoded chars and linebreaks
-    /// Linebreaks are ignored - the entire file is a read as a single encoded value
+    /// The file contains only the base64 encoded chars and whitespace
+    /// Whitespace is ignored - the entire file is a read as a single encoded value
     ///
     /// Errors
     /// If file can't be opened/read
     /// If file contains invalid base64 chars
     pub fn from_file(path: &str) -> Result<Base64, Error> {
-        let file_contents = fs::read_to_string(path)?;
-
-        let base64_string = file_contents.lines().collect::<Vec<&str>>().join("");
-
-        Base64::from_str(&base64_string)
+        Base64::from_reader(File::open(path)?, &Config::STANDARD.with_strict(false))
     }
 
     /// Tries to create a Vec<Base64> from a file
@@ -84,52 +83,43 @@ impl Base64 {
     /// If file contains invalid base64 chars
     /// If file contains multi-line encodings resulting in an invalid base64 encoding on a single line
     pub fn from_file_multi(path: &str) -> Result<Vec<Base64>, Error> {
-        let file_contents = fs::read_to_string(path)?;
-
-        let mut result = Vec::with_capacity(file_contents.lines().count());
-        for line in file_contents.lines() {
-            result.push(Base64::from_str(line)?);
+        let mut result = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            result.push(Base64::from_str(&line?)?);
         }
 
         Ok(result)
     }
 
-    /// Takes a slice of raw bytes and converts into base64 encoded bytes
-    /// Panics
-    /// If slice of raw bytes has len > 4
-    fn encode_bytes(bytes: &[u8]) -> [u8; 4] {
-        if bytes.len() > 4 {
-            panic!("encode_bytes given too many bytes");
-        }
-
-        let chunk_array = {
-            let mut chunk_array = [0u8; 4];
-            // final chunk may be less than 3 bytes
-            let chunk_start = 4 - bytes.len();
-            chunk_array[chunk_start..].copy_from_slice(bytes);
-            chunk_array
-        };
-
-        let value: u32 = u32::from_be_bytes(chunk_array);
-
-        let low_six_bits_mask = 0b11_1111;
-        let first_val = (value & low_six_bits_mask) as u8;
-        let second_val = (value >> 6 & low_six_bits_mask) as u8;
-        let third_val = (value >> 12 & low_six_bits_mask) as u8;
-        let fourth_val = (value >> 18 & low_six_bits_mask) as u8;
-
-        [fourth_val, third_val, second_val, first_val]
+    /// Decodes everything the reader produces as a single encoded value
+    ///
+    /// Errors
+    /// If the reader fails
+    /// If what it reads is not valid base64 for the configuration
+    pub fn from_reader<R: Read>(reader: R, config: &Config) -> Result<Base64, Error> {
+        let mut bytes = Vec::new();
+        DecoderReader::new(reader, *config).read_to_end(&mut bytes)?;
+        Ok(Base64 { bytes })
     }
 
+    /// Encodes the bytes with the standard alphabet and padding
     pub fn encode(bytes: &[u8]) -> String {
-        let mut result = String::new();
+        Config::STANDARD.encode(bytes)
+    }
 
-        for chunk in bytes.chunks(3) {
-            let encoded_bytes = Self::encode_bytes(chunk);
-            Self::string_from_encoded_bytes(&mut result, chunk.len(), encoded_bytes);
-        }
+    /// Decodes the string with the given configuration
+    ///
+    /// Errors
+    /// If the string is not valid base64 for the configuration
+    pub fn decode(s: &str, config: &Config) -> Result<Base64, Error> {
+        Ok(Base64 {
+            bytes: config.decode(s)?,
+        })
+    }
 
-        result
+    /// Encodes the bytes with the given configuration
+    pub fn to_string_with(&self, config: &Config) -> String {
+        config.encode(&self.bytes)
     }
 
     /// Truncates to the supplied length of bytes (not utf-8 chars or graphemes)
@@ -144,86 +134,446 @@ impl Base64 {
 
         self.bytes.truncate(len);
     }
+}
 
-    fn string_from_encoded_bytes(
-        result: &mut String,
-        raw_byte_length: usize,
-        encoded_bytes: [u8; 4],
-    ) {
-        if raw_byte_length == 3 {
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[0]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[1]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[2]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[3]));
-        } else if raw_byte_length == 2 {
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[0]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[1]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[2]));
-            result.push('=');
-        } else {
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[0]));
-            result.push(Self::ascii_char_from_encoded_byte(encoded_bytes[1]));
-            result.push('=');
-            result.push('=');
-        }
-    }
-
-    fn ascii_char_from_encoded_byte(converted: u8) -> char {
-        if converted < 26 {
-            (converted + 65) as char
-        } else if converted < 52 {
-            (converted + 71) as char
-        } else if converted < 62 {
-            (converted - 4) as char
-        } else if converted == 62 {
-            '+'
-        } else {
-            '/'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    /// `A-Z`, `a-z`, `0-9`, `+` and `/`
+    Standard,
+    /// `A-Z`, `a-z`, `0-9`, `-` and `_`, safe to use in URLs and filenames
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(&self) -> &'static [u8; 64] {
+        match self {
+            Alphabet::Standard => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"
+            }
+            Alphabet::UrlSafe => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+            }
+        }
+    }
+
+    fn value(&self, symbol: u8) -> Option<u8> {
+        self.symbols()
+            .iter()
+            .position(|s| *s == symbol)
+            .map(|value| value as u8)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Base64Error {
+    InvalidByte { byte: u8, offset: usize },
+    InvalidLength,
+    InvalidPadding { offset: usize },
+    MissingPadding,
+    TrailingBits,
+}
+
+impl Display for Base64Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Base64Error::InvalidByte { byte, offset } => {
+                write!(f, "invalid base64 byte {byte:#04x} at offset {offset}")
+            }
+            Base64Error::InvalidLength => {
+                write!(f, "a single base64 symbol can't encode a whole byte")
+            }
+            Base64Error::InvalidPadding { offset } => {
+                write!(f, "unexpected padding at offset {offset}")
+            }
+            Base64Error::MissingPadding => write!(f, "the final group is not padded"),
+            Base64Error::TrailingBits => {
+                write!(f, "the unused bits of the final symbol are not zero")
+            }
+        }
+    }
+}
+
+impl StdError for Base64Error {}
+
+/// How base64 is encoded and decoded
+///
+/// Strict decoding rejects whitespace, padding that doesn't match the configuration and non-zero
+/// unused bits in the final symbol; lenient decoding skips whitespace, accepts padding whether or
+/// not it is expected and ignores the unused bits. Both reject symbols outside the alphabet and
+/// anything after the padding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    alphabet: Alphabet,
+    padding: bool,
+    line_length: Option<usize>,
+    strict: bool,
+}
+
+impl Config {
+    /// The standard alphabet with padding, decoded strictly
+    pub const STANDARD: Config = Config::new(Alphabet::Standard);
+    /// The standard alphabet without padding, decoded strictly
+    pub const STANDARD_NO_PAD: Config = Config::new(Alphabet::Standard).with_padding(false);
+    /// The URL and filename safe alphabet with padding, decoded strictly
+    pub const URL_SAFE: Config = Config::new(Alphabet::UrlSafe);
+    /// The URL and filename safe alphabet without padding, decoded strictly
+    pub const URL_SAFE_NO_PAD: Config = Config::new(Alphabet::UrlSafe).with_padding(false);
+    /// The standard alphabet with padding, wrapped at 76 columns with CRLF line breaks as
+    /// required by MIME (RFC 2045), decoded leniently
+    pub const MIME: Config = Config::new(Alphabet::Standard)
+        .with_line_length(76)
+        .with_strict(false);
+
+    /// Pads its output, doesn't wrap lines and decodes strictly
+    pub const fn new(alphabet: Alphabet) -> Config {
+        Config {
+            alphabet,
+            padding: true,
+            line_length: None,
+            strict: true,
+        }
+    }
+
+    pub const fn with_padding(mut self, padding: bool) -> Config {
+        self.padding = padding;
+        self
+    }
+
+    /// Breaks the encoded output with CRLF every line_length symbols
+    ///
+    /// Panics
+    /// If the line length is zero
+    pub const fn with_line_length(mut self, line_length: usize) -> Config {
+        assert!(line_length > 0, "line length must be non-zero");
+        self.line_length = Some(line_length);
+        self
+    }
+
+    pub const fn with_strict(mut self, strict: bool) -> Config {
+        self.strict = strict;
+        self
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut writer = EncoderWriter::new(Vec::new(), *self);
+        writer
+            .write_all(bytes)
+            .expect("writing to a vec never fails");
+        let encoded = writer.finish().expect("writing to a vec never fails");
+        String::from_utf8(encoded).expect("base64 is ascii")
+    }
+
+    /// Errors
+    /// If the string is not valid base64 for the configuration
+    pub fn decode(&self, s: &str) -> Result<Vec<u8>, Error> {
+        let mut decoder = Decoder::new(*self);
+        let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+        for &symbol in s.as_bytes() {
+            decoder.push(symbol, &mut bytes)?;
+        }
+        decoder.finish(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Decodes one symbol at a time, holding on to an incomplete group of four
+struct Decoder {
+    config: Config,
+    group: [u8; 4],
+    symbols: usize,
+    padding: usize,
+    finished: bool,
+    offset: usize,
+}
+
+impl Decoder {
+    fn new(config: Config) -> Decoder {
+        Decoder {
+            config,
+            group: [0; 4],
+            symbols: 0,
+            padding: 0,
+            finished: false,
+            offset: 0,
+        }
+    }
+
+    fn push(&mut self, symbol: u8, output: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let offset = self.offset;
+        self.offset += 1;
+
+        if !self.config.strict && symbol.is_ascii_whitespace() {
+            return Ok(());
+        }
+
+        if symbol == b'=' {
+            let unexpected = self.config.strict && !self.config.padding;
+            if unexpected || self.finished || self.symbols < 2 {
+                return Err(Base64Error::InvalidPadding { offset });
+            }
+            self.padding += 1;
+            if self.symbols + self.padding == 4 {
+                self.decode_final_group(output)?;
+                self.finished = true;
+            }
+            return Ok(());
+        }
+
+        if self.finished || self.padding > 0 {
+            return Err(Base64Error::InvalidPadding { offset });
+        }
+        self.group[self.symbols] =
+            self.config
+                .alphabet
+                .value(symbol)
+                .ok_or(Base64Error::InvalidByte {
+                    byte: symbol,
+                    offset,
+                })?;
+        self.symbols += 1;
+
+        if self.symbols == 4 {
+            let value = self.value();
+            output.extend_from_slice(&value.to_be_bytes()[1..]);
+            self.symbols = 0;
+        }
+        Ok(())
+    }
+
+    /// Decodes whatever is left once there are no more symbols
+    fn finish(&mut self, output: &mut Vec<u8>) -> Result<(), Base64Error> {
+        if self.finished || (self.symbols == 0 && self.padding == 0) {
+            return Ok(());
         }
+        if self.config.strict && (self.padding > 0 || self.config.padding) {
+            return Err(Base64Error::MissingPadding);
+        }
+        self.decode_final_group(output)
     }
 
-    fn encoded_bytes_from_chunk(chunk: &[u8]) -> Vec<u8> {
-        if chunk.len() != 4 {
-            panic!("invalid chunk length");
+    /// Decodes a group of two or three symbols, which encode one or two bytes
+    fn decode_final_group(&mut self, output: &mut Vec<u8>) -> Result<(), Base64Error> {
+        if self.symbols < 2 {
+            return Err(Base64Error::InvalidLength);
+        }
+
+        let bytes = self.symbols - 1;
+        let value = self.value();
+        let unused_bits = value & ((1 << (8 * (3 - bytes))) - 1);
+        if self.config.strict && unused_bits != 0 {
+            return Err(Base64Error::TrailingBits);
         }
 
-        let chunk_value = chunk.iter().fold(0, |accum, chunk| {
-            (accum << 6) + (Self::decimal_value_from_ascii_byte(*chunk) as u32)
-        });
+        output.extend_from_slice(&value.to_be_bytes()[1..=bytes]);
+        self.symbols = 0;
+        Ok(())
+    }
+
+    /// The group as a 24 bit value, with any missing symbols as zero
+    fn value(&self) -> u32 {
+        self.group[..self.symbols]
+            .iter()
+            .chain([0; 4].iter())
+            .take(4)
+            .fold(0, |value, symbol| (value << 6) | *symbol as u32)
+    }
+}
+
+/// Base64 encodes everything written to it and passes it on to the inner writer
+///
+/// Whole groups of three bytes are encoded as soon as they are written; any left over are
+/// buffered until more data arrives. The final group, and its padding, is only written by
+/// [EncoderWriter::finish], which must be called to complete the encoding.
+///
+/// Encoded bytes the inner writer hasn't taken yet are kept, and passed on before anything else by
+/// the next write, flush or finish, so a write that fails can be retried without losing or
+/// repeating output
+pub struct EncoderWriter<W: Write> {
+    inner: W,
+    config: Config,
+    buffer: Vec<u8>,
+    column: usize,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncoderWriter<W> {
+    pub fn new(inner: W, config: Config) -> EncoderWriter<W> {
+        EncoderWriter {
+            inner,
+            config,
+            buffer: Vec::with_capacity(3),
+            column: 0,
+            pending: Vec::new(),
+        }
+    }
 
-        let last_byte_mask = 0b1111_1111;
+    /// Encodes the remaining buffered bytes, flushes the inner writer and returns it
+    pub fn finish(mut self) -> io::Result<W> {
+        let group = std::mem::take(&mut self.buffer);
+        self.encode_group(&group);
+        self.write_pending()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
 
-        vec![
-            (chunk_value >> 16 & last_byte_mask) as u8,
-            (chunk_value >> 8 & last_byte_mask) as u8,
-            (chunk_value & last_byte_mask) as u8,
-        ]
+    /// Passes the pending encoded bytes on to the inner writer, keeping any it doesn't take
+    fn write_pending(&mut self) -> io::Result<()> {
+        while !self.pending.is_empty() {
+            match self.inner.write(&self.pending) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
     }
 
-    fn decimal_value_from_ascii_byte(ascii_byte: u8) -> u8 {
-        let ascii_char = ascii_byte as char;
-        if ascii_char == '+' {
-            62
-        } else if ascii_char == '/' {
-            63
-        } else if ascii_char == '=' {
-            0
-        } else if ascii_byte < 58 {
-            ascii_byte + 4
-        } else if ascii_byte < 91 {
-            ascii_byte - 65
+    /// Encodes a group of up to three bytes onto the pending output
+    fn encode_group(&mut self, group: &[u8]) {
+        if group.is_empty() {
+            return;
+        }
+
+        let mut bytes = [0; 4];
+        bytes[1..=group.len()].copy_from_slice(group);
+        let value = u32::from_be_bytes(bytes);
+        let symbols = self.config.alphabet.symbols();
+
+        for i in 0..4 {
+            let symbol = if i <= group.len() {
+                symbols[(value >> (18 - 6 * i) & 0b11_1111) as usize]
+            } else if self.config.padding {
+                b'='
+            } else {
+                break;
+            };
+
+            if self.config.line_length == Some(self.column) {
+                self.pending.extend_from_slice(b"\r\n");
+                self.column = 0;
+            }
+            self.pending.push(symbol);
+            self.column += 1;
+        }
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // nothing is taken from buf until the output of earlier writes is through
+        self.write_pending()?;
+
+        self.buffer.extend_from_slice(buf);
+        let whole_groups = self.buffer.len() - self.buffer.len() % 3;
+        let groups: Vec<u8> = self.buffer.drain(..whole_groups).collect();
+        groups.chunks(3).for_each(|group| self.encode_group(group));
+
+        // buf has been taken either way; if this fails the next write, flush or finish retries it
+        let _ = self.write_pending();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
+
+/// Reads base64 from the inner reader and decodes it
+///
+/// Invalid base64 is reported as an [io::ErrorKind::InvalidData] error
+pub struct DecoderReader<R: Read> {
+    inner: R,
+    decoder: Decoder,
+    decoded: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecoderReader<R> {
+    pub fn new(inner: R, config: Config) -> DecoderReader<R> {
+        DecoderReader {
+            inner,
+            decoder: Decoder::new(config),
+            decoded: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    fn decode_next_chunk(&mut self) -> io::Result<()> {
+        let mut encoded = [0; 1024];
+        let read = self.inner.read(&mut encoded)?;
+
+        self.decoded.clear();
+        self.position = 0;
+        let result = if read == 0 {
+            self.finished = true;
+            self.decoder.finish(&mut self.decoded)
         } else {
-            ascii_byte - 71
+            encoded[..read]
+                .iter()
+                .try_for_each(|symbol| self.decoder.push(*symbol, &mut self.decoded))
+        };
+        result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<R: Read> Read for DecoderReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.decode_next_chunk()?;
         }
+
+        let available = &self.decoded[self.position..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read;
+        Ok(read)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::io::{self, Read, Write};
     use std::str::FromStr;
 
-    use crate::encoding::base64::Base64;
+    use crate::encoding::base64::{Alphabet, Base64, Config, DecoderReader, EncoderWriter};
     use crate::encoding::Digest;
+    use crate::test_util::FlakyWriter;
+
+    /// The test vectors from RFC 4648 section 10
+    const RFC_4648: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    /// Hands out its data a byte at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(first)) => {
+                    *first = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
 
     #[test]
     fn base_64_from_bytes() {
@@ -244,4 +594,127 @@ mod test {
 
         assert_eq!(expected_bytes, calculated_base64.bytes())
     }
+
+    #[test]
+    fn rfc_4648_vectors() {
+        for (plain, encoded) in RFC_4648 {
+            assert_eq!(encoded, Base64::encode(plain.as_bytes()));
+            assert_eq!(plain.as_bytes(), Base64::from_str(encoded).unwrap().bytes());
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(unpadded, Config::STANDARD_NO_PAD.encode(plain.as_bytes()));
+            assert_eq!(
+                plain.as_bytes(),
+                Config::STANDARD_NO_PAD.decode(unpadded).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn url_safe_alphabet() {
+        let bytes = [0xfb, 0xff, 0xbf];
+        assert_eq!("+/+/", Config::STANDARD.encode(&bytes));
+        assert_eq!("-_-_", Config::URL_SAFE.encode(&bytes));
+        assert_eq!("-_8", Config::URL_SAFE_NO_PAD.encode(&bytes[..2]));
+        assert_eq!(bytes, &Config::URL_SAFE.decode("-_-_").unwrap()[..]);
+
+        assert!(Config::URL_SAFE.decode("+/+/").is_err());
+        assert!(Config::STANDARD.decode("-_-_").is_err());
+        assert_eq!(
+            Config::new(Alphabet::UrlSafe).with_padding(false),
+            Config::URL_SAFE_NO_PAD
+        );
+    }
+
+    #[test]
+    fn mime_wraps_at_76_columns() {
+        let bytes = [0xa5; 58];
+        let encoded = Config::MIME.encode(&bytes);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+
+        assert_eq!(2, lines.len());
+        assert_eq!(76, lines[0].len());
+        assert_eq!("pQ==", lines[1]);
+        assert_eq!(76, Config::MIME.encode(&bytes[..57]).len());
+        assert_eq!(&bytes[..], Config::MIME.decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn strict_decoding() {
+        for invalid in [
+            "Zg",         // missing padding
+            "Zg=",        // incomplete padding
+            "Zh==",       // unused bits set
+            "Z===",       // too much padding
+            "Z",          // a lone symbol
+            "Zg==Zg==",   // data after padding
+            "Zm9v\nYg==", // whitespace
+            "Zm9v*g==",   // outside the alphabet
+        ] {
+            assert!(Config::STANDARD.decode(invalid).is_err(), "{invalid}");
+        }
+        assert!(Config::STANDARD_NO_PAD.decode("Zg==").is_err());
+    }
+
+    #[test]
+    fn lenient_decoding() {
+        let lenient = Config::STANDARD.with_strict(false);
+        for (valid, expected) in [
+            ("Zg", "f"),
+            ("Zg=", "f"),
+            ("Zh==", "f"),
+            (" Zm9v\r\nYg==\n", "foob"),
+            ("Zm 9v Ym Fy", "foobar"),
+        ] {
+            assert_eq!(
+                expected.as_bytes(),
+                lenient.decode(valid).unwrap(),
+                "{valid}"
+            );
+        }
+        for invalid in ["Z", "Zg==Zg==", "Zm9v*g=="] {
+            assert!(lenient.decode(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn failed_write_loses_nothing() {
+        let bytes: Vec<u8> = (0..=255).cycle().take(200).collect();
+        // fails outright, then part way through a write_all
+        for (chunk, fail_on) in [(usize::MAX, 2), (5, 4)] {
+            let mut writer = EncoderWriter::new(FlakyWriter::new(chunk, fail_on), Config::MIME);
+            for chunk in bytes.chunks(50) {
+                while writer.write(chunk).is_err() {}
+            }
+            let inner = writer.finish().unwrap();
+
+            assert!(inner.failed());
+            assert_eq!(Config::MIME.encode(&bytes).as_bytes(), inner.written);
+        }
+    }
+
+    #[test]
+    fn streams_match_slices() {
+        let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for config in [Config::STANDARD, Config::URL_SAFE_NO_PAD, Config::MIME] {
+            let mut writer = EncoderWriter::new(Vec::new(), config);
+            for chunk in bytes.chunks(7) {
+                writer.write_all(chunk).unwrap();
+            }
+            let encoded = writer.finish().unwrap();
+            assert_eq!(config.encode(&bytes).as_bytes(), encoded);
+
+            let mut decoded = Vec::new();
+            DecoderReader::new(Trickle(&encoded), config)
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(bytes, decoded);
+        }
+
+        for (plain, encoded) in RFC_4648 {
+            let base64 = Base64::from_reader(encoded.as_bytes(), &Config::STANDARD).unwrap();
+            assert_eq!(plain.as_bytes(), base64.bytes());
+        }
+        assert!(Base64::from_reader("Zg".as_bytes(), &Config::STANDARD).is_err());
+    }
 }
@@ -0,0 +1,313 @@
+//! Works out how a string has been encoded, peeling off as many layers as it can
+//!
+//! Every encoding whose shape the string fits is tried, and the result decoded again until
+//! nothing more fits, so `hex(base64(cipher text))` comes back as the cipher text with the layers
+//! `[Hex, Base64]`. An encoding is only tried if the string really looks like it: hex must be at
+//! least 8 digits, base64 at least 8 symbols, and URL encoding must contain a `%XX` escape, so
+//! short words like `test` or `cafe` are left alone.
+//!
+//! The string itself and every decoding along the way are all candidates. Those that are surely
+//! still encoded, such as a long run of hex digits, are ranked last; the rest are ranked by
+//! [Digest::english_score], then by how confident each layer was. A plain word that happens to be
+//! valid base64 is kept as it is, since the bytes it decodes to score worse than the word
+use std::str::FromStr;
+
+use crate::encoding::base64::Config;
+use crate::encoding::hex::Hex;
+use crate::encoding::Digest;
+
+/// The deepest chain of encodings that is unwrapped
+const MAX_LAYERS: usize = 8;
+/// The most results explored, to bound strings that fit several encodings at every layer
+const MAX_CANDIDATES: usize = 256;
+/// The shortest hex or base64 string that is decoded
+const MIN_ENCODED_LENGTH: usize = 8;
+/// The shortest base64 string that is taken to be encoded rather than a word that happens to fit
+const MIN_CERTAIN_BASE64_LENGTH: usize = 16;
+/// The confidence above which a string is taken to be encoded, and so not a reading of its own
+const CERTAIN: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    /// The standard alphabet with padding
+    Base64,
+    /// The standard alphabet without padding
+    Base64NoPad,
+    /// The URL and filename safe alphabet with padding
+    Base64UrlSafe,
+    /// The URL and filename safe alphabet without padding
+    Base64UrlSafeNoPad,
+    /// The standard alphabet with padding, broken over several lines
+    Base64Mime,
+    /// Percent encoding as described in RFC 3986; `+` is left as it is
+    Url,
+}
+
+const ENCODINGS: [Encoding; 7] = [
+    Encoding::Hex,
+    Encoding::Base64,
+    Encoding::Base64NoPad,
+    Encoding::Base64UrlSafe,
+    Encoding::Base64UrlSafeNoPad,
+    Encoding::Base64Mime,
+    Encoding::Url,
+];
+
+impl Encoding {
+    /// Decodes the string if it has the shape of this encoding, along with how confident that
+    /// makes the decoding
+    fn decode(&self, s: &str) -> Option<(Vec<u8>, f64)> {
+        let length = s.len();
+        let is_hex = s.bytes().all(|byte| byte.is_ascii_hexdigit());
+        let url_safe = s.contains(['-', '_']);
+        // a string of hex digits is also valid base64, but much more likely to be hex, and a
+        // short string could just as well be a word
+        let base64_confidence = if is_hex {
+            0.2
+        } else if length < MIN_CERTAIN_BASE64_LENGTH {
+            0.5
+        } else {
+            CERTAIN
+        };
+
+        let decoded = match self {
+            Encoding::Hex if is_hex && length >= MIN_ENCODED_LENGTH && length.is_multiple_of(2) => {
+                (
+                    Hex::from_str(&s.to_ascii_lowercase())
+                        .ok()?
+                        .bytes()
+                        .to_vec(),
+                    1.0,
+                )
+            }
+            Encoding::Base64 if length >= MIN_ENCODED_LENGTH => {
+                (Config::STANDARD.decode(s).ok()?, base64_confidence)
+            }
+            Encoding::Base64NoPad if length >= MIN_ENCODED_LENGTH && !length.is_multiple_of(4) => {
+                (Config::STANDARD_NO_PAD.decode(s).ok()?, base64_confidence)
+            }
+            Encoding::Base64UrlSafe if length >= MIN_ENCODED_LENGTH && url_safe => {
+                (Config::URL_SAFE.decode(s).ok()?, base64_confidence)
+            }
+            Encoding::Base64UrlSafeNoPad
+                if length >= MIN_ENCODED_LENGTH && url_safe && !length.is_multiple_of(4) =>
+            {
+                (Config::URL_SAFE_NO_PAD.decode(s).ok()?, base64_confidence)
+            }
+            // only line breaks, so that sentences aren't read as base64 with spaces in
+            Encoding::Base64Mime if s.trim_end().contains('\n') && !s.contains([' ', '\t']) => {
+                (Config::MIME.decode(s).ok()?, CERTAIN)
+            }
+            Encoding::Url if has_percent_escape(s) => (percent_decode(s)?, 1.0),
+            _ => return None,
+        };
+        Some(decoded)
+    }
+}
+
+/// A way of decoding a string, and what it decodes to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub bytes: Vec<u8>,
+    /// The encodings that were removed, outermost first; empty if the string was left as it is
+    pub layers: Vec<Encoding>,
+    /// The product of how confident each layer was, between 0 and 1
+    pub confidence: f64,
+}
+
+impl Digest for Detection {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Digest for &Detection {
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// The likeliest decoding of the string, which is the string itself if it doesn't look encoded
+pub fn detect(s: &str) -> Detection {
+    candidates(s)
+        .into_iter()
+        .next()
+        .expect("the string itself is always a candidate")
+}
+
+/// Every reading of the string, likeliest first, starting with the string itself and including
+/// each layer along the way
+///
+/// Readings that are surely still encoded come last; the rest are ranked by their
+/// [Digest::english_score], then by their confidence, then by how many layers were removed
+pub fn candidates(s: &str) -> Vec<Detection> {
+    // each reading, and whether it is surely encoded
+    let mut explored = Vec::new();
+    let mut pending = vec![Detection {
+        bytes: s.as_bytes().to_vec(),
+        layers: Vec::new(),
+        confidence: 1.0,
+    }];
+
+    while let Some(detection) = pending.pop() {
+        let decodings: Vec<(Detection, f64)> = match std::str::from_utf8(&detection.bytes) {
+            Ok(s) if detection.layers.len() < MAX_LAYERS && explored.len() < MAX_CANDIDATES => {
+                ENCODINGS
+                    .iter()
+                    .filter_map(|encoding| {
+                        let (bytes, confidence) = encoding.decode(s)?;
+                        let mut layers = detection.layers.clone();
+                        layers.push(*encoding);
+                        Some((
+                            Detection {
+                                bytes,
+                                layers,
+                                confidence: detection.confidence * confidence,
+                            },
+                            confidence,
+                        ))
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        let encoded = decodings
+            .iter()
+            .any(|(_, confidence)| *confidence >= CERTAIN);
+        pending.extend(decodings.into_iter().map(|(decoding, _)| decoding));
+        explored.push((detection, encoded));
+    }
+
+    explored.sort_by(|(a, a_encoded), (b, b_encoded)| {
+        a_encoded
+            .cmp(b_encoded)
+            .then(b.english_score().cmp(&a.english_score()))
+            .then(b.confidence.total_cmp(&a.confidence))
+            .then(b.layers.len().cmp(&a.layers.len()))
+    });
+    explored
+        .into_iter()
+        .map(|(detection, _)| detection)
+        .collect()
+}
+
+fn has_percent_escape(s: &str) -> bool {
+    s.as_bytes().windows(3).any(|window| {
+        window[0] == b'%' && window[1].is_ascii_hexdigit() && window[2].is_ascii_hexdigit()
+    })
+}
+
+/// Replaces each `%XX` escape with the byte it encodes, none if a `%` isn't followed by two hex
+/// digits
+fn percent_decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let escape = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(escape, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoding::base64::{Base64, Config};
+    use crate::encoding::detect::{candidates, detect, Encoding};
+    use crate::encoding::hex::Hex;
+
+    const PLAIN_TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn hex_of_base64_of_cipher_text() {
+        let cipher_text: Vec<u8> = (0..48u8).map(|i| i.wrapping_mul(151) ^ 0xa5).collect();
+        let encoded = Hex::new(Base64::encode(&cipher_text).as_bytes()).to_string();
+
+        let detection = detect(&encoded);
+        assert_eq!(detection.layers, vec![Encoding::Hex, Encoding::Base64]);
+        assert_eq!(detection.bytes, cipher_text);
+    }
+
+    #[test]
+    fn base64_of_english() {
+        let detection = detect(&Base64::encode(PLAIN_TEXT.as_bytes()));
+        assert_eq!(detection.layers, vec![Encoding::Base64]);
+        assert_eq!(detection.bytes, PLAIN_TEXT.as_bytes());
+    }
+
+    #[test]
+    fn uppercase_hex_of_english() {
+        let encoded = Hex::new(PLAIN_TEXT.as_bytes()).to_string().to_uppercase();
+        let detection = detect(&encoded);
+        assert_eq!(detection.layers, vec![Encoding::Hex]);
+        assert_eq!(detection.bytes, PLAIN_TEXT.as_bytes());
+    }
+
+    #[test]
+    fn url_safe_and_mime_base64() {
+        let plain_text = "what?>> who?? why~~~";
+        let encoded = Config::URL_SAFE_NO_PAD.encode(plain_text.as_bytes());
+        assert!(encoded.contains(['-', '_']) && !encoded.len().is_multiple_of(4));
+        let detection = detect(&encoded);
+        assert_eq!(detection.layers, vec![Encoding::Base64UrlSafeNoPad]);
+        assert_eq!(detection.bytes, plain_text.as_bytes());
+
+        let plain_text = PLAIN_TEXT.repeat(3);
+        let detection = detect(&Config::MIME.encode(plain_text.as_bytes()));
+        assert_eq!(detection.layers, vec![Encoding::Base64Mime]);
+        assert_eq!(detection.bytes, plain_text.as_bytes());
+    }
+
+    #[test]
+    fn url_of_base64_of_hex() {
+        let plain_text = "it's a secret > to everybody";
+        let base64 = Base64::encode(Hex::new(plain_text.as_bytes()).to_string().as_bytes());
+        let encoded = base64
+            .replace('+', "%2B")
+            .replace('/', "%2F")
+            .replace('=', "%3D");
+
+        let detection = detect(&encoded);
+        assert_eq!(
+            detection.layers,
+            vec![Encoding::Url, Encoding::Base64, Encoding::Hex]
+        );
+        assert_eq!(detection.bytes, plain_text.as_bytes());
+    }
+
+    #[test]
+    fn plain_text_is_left_alone() {
+        for plain_text in [PLAIN_TEXT, "test", "cafe", "100%"] {
+            let detection = detect(plain_text);
+            assert!(detection.layers.is_empty(), "{plain_text}");
+            assert_eq!(detection.bytes, plain_text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn words_that_are_valid_base64_are_left_alone() {
+        for word in ["Password", "SecretKey123"] {
+            assert!(Config::STANDARD.decode(word).is_ok(), "{word}");
+            let detection = detect(word);
+            assert!(detection.layers.is_empty(), "{word}");
+            assert_eq!(detection.bytes, word.as_bytes());
+        }
+    }
+
+    #[test]
+    fn candidates_include_every_reading() {
+        let all = candidates("deadbeefcafebabe");
+        assert_eq!(all[0].layers, vec![Encoding::Hex]);
+        assert!(all.iter().any(|c| c.layers == vec![Encoding::Base64]));
+        assert!(all
+            .iter()
+            .any(|c| c.layers.is_empty() && c.bytes == "deadbeefcafebabe".as_bytes()));
+    }
+}
@@ -12,7 +12,9 @@ enum CookieEncodingError {
 
 impl Display for CookieEncodingError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            ValuePairFormatInvalid(message) => write!(f, "{message}"),
+        }
     }
 }
 
@@ -659,8 +659,6 @@ impl<'de> serde::de::Deserialize<'de> for Digest {
 #[rustfmt::skip]
 #[cfg(test)]
 mod tests {
-    use rand::{Rng, RngCore};
-
     use crate::hashes::sha_1::Sha1;
 
     #[test]
@@ -741,7 +739,10 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "openssl")]
     fn spray_and_pray() {
+        use rand::{Rng, RngCore};
+
         let mut rng = rand::thread_rng();
         let mut m = Sha1::new();
         let mut bytes = [0; 512];
@@ -7,6 +7,8 @@ pub mod kex;
 pub mod mac;
 pub mod profile;
 pub mod random;
+#[cfg(test)]
+mod test_util;
 
 pub type Error = Box<dyn std::error::Error>;
 
@@ -16,7 +16,9 @@ impl stdError for ProfileErrors {}
 
 impl Display for ProfileErrors {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            MissingValue(message) => write!(f, "{message}"),
+        }
     }
 }
 
@@ -41,12 +43,17 @@ impl ProfileEncrypter {
         ecb::encrypt(&self.key, structured_cookie.to_string().into_bytes())
     }
 
+    /// Panics
+    /// If the cipher text does not decrypt to a valid profile
     pub fn decrypt(&self, cipher_text: &[u8]) -> Profile {
-        let plain_text = ecb::decrypt(&self.key, cipher_text.to_vec());
-        StructuredCookie::from_str(&String::from_utf8(plain_text).unwrap())
-            .unwrap()
-            .try_into()
-            .unwrap()
+        self.try_decrypt(cipher_text).expect("invalid profile")
+    }
+
+    /// Errors
+    /// If the cipher text is not correctly padded or does not decrypt to a valid profile cookie
+    pub fn try_decrypt(&self, cipher_text: &[u8]) -> Result<Profile, Error> {
+        let plain_text = ecb::try_decrypt(&self.key, cipher_text.to_vec())?;
+        StructuredCookie::from_str(&String::from_utf8(plain_text)?)?.try_into()
     }
 }
 
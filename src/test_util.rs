@@ -0,0 +1,43 @@
+//! Fixtures shared by the tests of several modules
+use std::io::{self, Write};
+
+/// Takes at most `chunk` bytes from each write and fails the `fail_on`th write, so a `write_all`
+/// can fail part way through
+pub(crate) struct FlakyWriter {
+    pub(crate) written: Vec<u8>,
+    chunk: usize,
+    fail_on: usize,
+    calls: usize,
+}
+
+impl FlakyWriter {
+    pub(crate) fn new(chunk: usize, fail_on: usize) -> FlakyWriter {
+        FlakyWriter {
+            written: Vec::new(),
+            chunk,
+            fail_on,
+            calls: 0,
+        }
+    }
+
+    /// Whether the write that was set to fail has been made
+    pub(crate) fn failed(&self) -> bool {
+        self.calls >= self.fail_on
+    }
+}
+
+impl Write for FlakyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        if self.calls == self.fail_on {
+            return Err(io::Error::other("connection reset"));
+        }
+        let written = buf.len().min(self.chunk);
+        self.written.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}